@@ -0,0 +1,266 @@
+use std::cmp::Reverse;
+use std::ops::{Deref, DerefMut};
+
+use crate::heaps::PriorityQueue;
+use crate::sort::heap::{build_max_heap, max_heapify, parent};
+
+// A max-heap stored in a `Vec` with the layout `sort::heap` works on.
+pub struct BinaryHeap<T> {
+    data: Vec<T>,
+}
+
+impl<T: Ord> BinaryHeap<T> {
+    pub fn new() -> Self {
+        Self { data: Vec::new() }
+    }
+
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    pub fn peek(&self) -> Option<&T> {
+        self.data.first()
+    }
+
+    // The returned guard restores the heap order when it is dropped, so the
+    // top element can be changed in place.
+    pub fn peek_mut(&mut self) -> Option<PeekMut<'_, T>> {
+        if self.is_empty() {
+            None
+        } else {
+            Some(PeekMut { heap: self })
+        }
+    }
+
+    pub fn push(&mut self, value: T) {
+        self.data.push(value);
+        let mut i = self.data.len() - 1;
+
+        while i > 0 && self.data[parent(i)] < self.data[i] {
+            self.data.swap(i, parent(i));
+            i = parent(i);
+        }
+    }
+
+    pub fn pop(&mut self) -> Option<T> {
+        if self.data.is_empty() {
+            return None;
+        }
+
+        let top = self.data.swap_remove(0);
+        let len = self.data.len();
+        max_heapify(&mut self.data, 0, len);
+        Some(top)
+    }
+
+    // The elements in arbitrary order.
+    pub fn into_vec(self) -> Vec<T> {
+        self.data
+    }
+
+    // The elements in ascending order.
+    pub fn into_sorted_vec(mut self) -> Vec<T> {
+        for end in (1..self.data.len()).rev() {
+            self.data.swap(0, end);
+            max_heapify(&mut self.data, 0, end);
+        }
+
+        self.data
+    }
+}
+
+impl<T: Ord> Default for BinaryHeap<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Ord> From<Vec<T>> for BinaryHeap<T> {
+    fn from(mut data: Vec<T>) -> Self {
+        build_max_heap(&mut data);
+        Self { data }
+    }
+}
+
+impl<T: Ord> FromIterator<T> for BinaryHeap<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        Self::from(Vec::from_iter(iter))
+    }
+}
+
+impl<T: Ord> Extend<T> for BinaryHeap<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for value in iter {
+            self.push(value);
+        }
+    }
+}
+
+impl<T: Ord> PriorityQueue<T> for BinaryHeap<T> {
+    fn push(&mut self, value: T) {
+        BinaryHeap::push(self, value);
+    }
+
+    fn pop(&mut self) -> Option<T> {
+        BinaryHeap::pop(self)
+    }
+
+    fn peek(&self) -> Option<&T> {
+        BinaryHeap::peek(self)
+    }
+
+    fn len(&self) -> usize {
+        BinaryHeap::len(self)
+    }
+}
+
+pub struct PeekMut<'a, T: Ord> {
+    heap: &'a mut BinaryHeap<T>,
+}
+
+impl<T: Ord> Deref for PeekMut<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.heap.data[0]
+    }
+}
+
+impl<T: Ord> DerefMut for PeekMut<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.heap.data[0]
+    }
+}
+
+impl<T: Ord> Drop for PeekMut<'_, T> {
+    fn drop(&mut self) {
+        let len = self.heap.data.len();
+        max_heapify(&mut self.heap.data, 0, len);
+    }
+}
+
+// A min-heap: a `BinaryHeap` over `Reverse`d elements that unwraps them on
+// the way out.
+pub struct MinHeap<T> {
+    heap: BinaryHeap<Reverse<T>>,
+}
+
+impl<T: Ord> MinHeap<T> {
+    pub fn new() -> Self {
+        Self {
+            heap: BinaryHeap::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.heap.is_empty()
+    }
+
+    pub fn peek(&self) -> Option<&T> {
+        self.heap.peek().map(|Reverse(value)| value)
+    }
+
+    pub fn push(&mut self, value: T) {
+        self.heap.push(Reverse(value));
+    }
+
+    pub fn pop(&mut self) -> Option<T> {
+        self.heap.pop().map(|Reverse(value)| value)
+    }
+}
+
+impl<T: Ord> Default for MinHeap<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Ord> From<Vec<T>> for MinHeap<T> {
+    fn from(data: Vec<T>) -> Self {
+        Self {
+            heap: data.into_iter().map(Reverse).collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn is_heap<T: Ord>(data: &[T]) -> bool {
+        (1..data.len()).all(|i| data[parent(i)] >= data[i])
+    }
+
+    #[test]
+    fn push_pop() {
+        let mut heap = BinaryHeap::new();
+        assert_eq!(heap.pop(), None);
+        assert_eq!(heap.peek(), None);
+
+        for value in [5, 2, 8, 1, 9, 3, 8] {
+            heap.push(value);
+            assert!(is_heap(&heap.data));
+        }
+
+        assert_eq!(heap.len(), 7);
+        assert_eq!(heap.peek(), Some(&9));
+
+        let mut popped = Vec::new();
+
+        while let Some(value) = heap.pop() {
+            assert!(is_heap(&heap.data));
+            popped.push(value);
+        }
+
+        assert_eq!(popped, [9, 8, 8, 5, 3, 2, 1]);
+        assert!(heap.is_empty());
+    }
+
+    #[test]
+    fn peek_mut_restores_order() {
+        let mut heap = BinaryHeap::from(vec![4, 1, 3, 2, 16, 9, 10, 14, 8, 7]);
+        assert!(is_heap(&heap.data));
+
+        *heap.peek_mut().unwrap() = 0;
+        assert!(is_heap(&heap.data));
+        assert_eq!(heap.peek(), Some(&14));
+
+        if let Some(mut top) = heap.peek_mut() {
+            *top += 100;
+        }
+
+        assert_eq!(heap.peek(), Some(&114));
+        assert!(BinaryHeap::<u8>::new().peek_mut().is_none());
+    }
+
+    #[test]
+    fn into_sorted_vec() {
+        let heap: BinaryHeap<_> = [5, 2, 4, 6, 1, 3].into_iter().collect();
+        assert_eq!(heap.into_sorted_vec(), [1, 2, 3, 4, 5, 6]);
+
+        let mut heap = BinaryHeap::new();
+        heap.extend("heap".chars());
+        assert_eq!(heap.into_sorted_vec(), ['a', 'e', 'h', 'p']);
+    }
+
+    #[test]
+    fn min_heap() {
+        let mut heap = MinHeap::from(vec![5, 2, 8]);
+        heap.push(1);
+        heap.push(9);
+
+        assert_eq!(heap.peek(), Some(&1));
+        assert_eq!(heap.len(), 5);
+
+        let popped: Vec<_> = std::iter::from_fn(|| heap.pop()).collect();
+        assert_eq!(popped, [1, 2, 5, 8, 9]);
+    }
+}
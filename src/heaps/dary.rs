@@ -0,0 +1,128 @@
+use crate::heaps::PriorityQueue;
+use crate::sort::heap::{build_max_heap_dary, max_heapify_dary, parent_dary};
+
+// A max-heap where every node has `D` children. Pushes touch only
+// log_D(n) levels, pops compare up to `D` children per level.
+pub struct DaryHeap<T, const D: usize> {
+    data: Vec<T>,
+}
+
+impl<T: Ord, const D: usize> DaryHeap<T, D> {
+    pub fn new() -> Self {
+        assert!(D >= 2, "a heap needs at least two children per node");
+        Self { data: Vec::new() }
+    }
+
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    pub fn peek(&self) -> Option<&T> {
+        self.data.first()
+    }
+
+    pub fn push(&mut self, value: T) {
+        self.data.push(value);
+        let mut i = self.data.len() - 1;
+
+        while i > 0 && self.data[parent_dary(i, D)] < self.data[i] {
+            self.data.swap(i, parent_dary(i, D));
+            i = parent_dary(i, D);
+        }
+    }
+
+    pub fn pop(&mut self) -> Option<T> {
+        if self.data.is_empty() {
+            return None;
+        }
+
+        let top = self.data.swap_remove(0);
+        let len = self.data.len();
+        max_heapify_dary(&mut self.data, D, 0, len);
+        Some(top)
+    }
+
+    // The elements in ascending order.
+    pub fn into_sorted_vec(mut self) -> Vec<T> {
+        for end in (1..self.data.len()).rev() {
+            self.data.swap(0, end);
+            max_heapify_dary(&mut self.data, D, 0, end);
+        }
+
+        self.data
+    }
+}
+
+impl<T: Ord, const D: usize> Default for DaryHeap<T, D> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Ord, const D: usize> From<Vec<T>> for DaryHeap<T, D> {
+    fn from(mut data: Vec<T>) -> Self {
+        build_max_heap_dary(&mut data, D);
+        Self { data }
+    }
+}
+
+impl<T: Ord, const D: usize> PriorityQueue<T> for DaryHeap<T, D> {
+    fn push(&mut self, value: T) {
+        DaryHeap::push(self, value);
+    }
+
+    fn pop(&mut self) -> Option<T> {
+        DaryHeap::pop(self)
+    }
+
+    fn peek(&self) -> Option<&T> {
+        DaryHeap::peek(self)
+    }
+
+    fn len(&self) -> usize {
+        DaryHeap::len(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn is_heap<T: Ord, const D: usize>(heap: &DaryHeap<T, D>) -> bool {
+        (1..heap.data.len()).all(|i| heap.data[parent_dary(i, D)] >= heap.data[i])
+    }
+
+    #[test]
+    fn push_pop() {
+        let mut heap = DaryHeap::<_, 3>::new();
+        assert_eq!(heap.pop(), None);
+
+        for value in [5, 2, 8, 1, 9, 3, 8, 7, 0, 4] {
+            heap.push(value);
+            assert!(is_heap(&heap));
+        }
+
+        assert_eq!(heap.peek(), Some(&9));
+        assert_eq!(heap.pop(), Some(9));
+        assert_eq!(heap.pop(), Some(8));
+        assert!(is_heap(&heap));
+        assert_eq!(heap.len(), 8);
+    }
+
+    #[test]
+    fn from_vec() {
+        let heap = DaryHeap::<_, 4>::from(vec![4, 1, 3, 2, 16, 9, 10, 14, 8, 7]);
+        assert!(is_heap(&heap));
+        assert_eq!(heap.into_sorted_vec(), [1, 2, 3, 4, 7, 8, 9, 10, 14, 16]);
+    }
+
+    #[test]
+    #[should_panic(expected = "at least two children")]
+    fn unary_heap() {
+        DaryHeap::<u8, 1>::new();
+    }
+}
@@ -0,0 +1,212 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use crate::sort::heap::{left, parent, right};
+
+// A max-heap on priorities laid out like `sort::heap`, with a map from each
+// key to its slot so entries can be found and moved in O(log n). Wrap
+// priorities in `Reverse` to pop the smallest first, as Dijkstra and Prim
+// want.
+pub struct IndexedHeap<K, P> {
+    data: Vec<(K, P)>,
+    pos: HashMap<K, usize>,
+}
+
+impl<K: Hash + Eq + Clone, P: Ord> IndexedHeap<K, P> {
+    pub fn new() -> Self {
+        Self {
+            data: Vec::new(),
+            pos: HashMap::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    pub fn contains(&self, key: &K) -> bool {
+        self.pos.contains_key(key)
+    }
+
+    pub fn priority(&self, key: &K) -> Option<&P> {
+        self.pos.get(key).map(|&i| &self.data[i].1)
+    }
+
+    pub fn peek(&self) -> Option<(&K, &P)> {
+        self.data.first().map(|(key, priority)| (key, priority))
+    }
+
+    // Inserts `key`, or changes its priority and returns the old one if it is
+    // already queued.
+    pub fn push(&mut self, key: K, priority: P) -> Option<P> {
+        if self.contains(&key) {
+            return self.change_priority(&key, priority);
+        }
+
+        self.pos.insert(key.clone(), self.data.len());
+        self.data.push((key, priority));
+        self.sift_up(self.data.len() - 1);
+        None
+    }
+
+    pub fn pop(&mut self) -> Option<(K, P)> {
+        let key = self.data.first()?.0.clone();
+        let priority = self.remove(&key)?;
+        Some((key, priority))
+    }
+
+    pub fn change_priority(&mut self, key: &K, priority: P) -> Option<P> {
+        let i = *self.pos.get(key)?;
+        let old = std::mem::replace(&mut self.data[i].1, priority);
+
+        if self.data[i].1 > old {
+            self.sift_up(i);
+        } else {
+            self.sift_down(i);
+        }
+
+        Some(old)
+    }
+
+    pub fn remove(&mut self, key: &K) -> Option<P> {
+        let i = self.pos.remove(key)?;
+        let (_, priority) = self.data.swap_remove(i);
+
+        // The last entry moved into the hole and may belong above or below it.
+        if i < self.data.len() {
+            self.pos.insert(self.data[i].0.clone(), i);
+            self.sift_up(i);
+            self.sift_down(i);
+        }
+
+        Some(priority)
+    }
+
+    fn swap(&mut self, i: usize, j: usize) {
+        self.data.swap(i, j);
+        self.pos.insert(self.data[i].0.clone(), i);
+        self.pos.insert(self.data[j].0.clone(), j);
+    }
+
+    fn sift_up(&mut self, mut i: usize) {
+        while i > 0 && self.data[parent(i)].1 < self.data[i].1 {
+            self.swap(i, parent(i));
+            i = parent(i);
+        }
+    }
+
+    fn sift_down(&mut self, mut i: usize) {
+        loop {
+            let mut largest = i;
+
+            for child in [left(i), right(i)] {
+                if child < self.data.len() && self.data[child].1 > self.data[largest].1 {
+                    largest = child;
+                }
+            }
+
+            if largest == i {
+                break;
+            }
+
+            self.swap(i, largest);
+            i = largest;
+        }
+    }
+}
+
+impl<K: Hash + Eq + Clone, P: Ord> Default for IndexedHeap<K, P> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::XorShift;
+    use std::cmp::Reverse;
+
+    fn check<K: Hash + Eq + Clone, P: Ord>(heap: &IndexedHeap<K, P>) {
+        assert_eq!(heap.pos.len(), heap.data.len());
+
+        for (i, (key, priority)) in heap.data.iter().enumerate() {
+            assert_eq!(heap.pos[key], i);
+
+            if i > 0 {
+                assert!(heap.data[parent(i)].1 >= *priority);
+            }
+        }
+    }
+
+    #[test]
+    fn operations_match_brute_force() {
+        let mut rng = XorShift::new(17);
+        let mut heap = IndexedHeap::new();
+        let mut brute: HashMap<u64, u64> = HashMap::new();
+
+        for _ in 0..5000 {
+            let key = rng.next_below(100);
+            let priority = rng.next_below(1000);
+
+            match rng.next_below(4) {
+                0 => assert_eq!(heap.push(key, priority), brute.insert(key, priority)),
+                1 => {
+                    let expected = brute.get_mut(&key).map(|p| std::mem::replace(p, priority));
+                    assert_eq!(heap.change_priority(&key, priority), expected);
+                }
+                2 => assert_eq!(heap.remove(&key), brute.remove(&key)),
+                _ => match heap.pop() {
+                    Some((key, priority)) => {
+                        assert_eq!(brute.values().max(), Some(&priority));
+                        assert_eq!(brute.remove(&key), Some(priority));
+                    }
+                    None => assert!(brute.is_empty()),
+                },
+            }
+
+            check(&heap);
+            assert_eq!(heap.len(), brute.len());
+            assert_eq!(heap.contains(&key), brute.contains_key(&key));
+        }
+    }
+
+    #[test]
+    fn dijkstra() {
+        let edges: [&[(usize, u32)]; 6] = [
+            &[(1, 7), (2, 9), (5, 14)],
+            &[(0, 7), (2, 10), (3, 15)],
+            &[(0, 9), (1, 10), (3, 11), (5, 2)],
+            &[(1, 15), (2, 11), (4, 6)],
+            &[(3, 6), (5, 9)],
+            &[(0, 14), (2, 2), (4, 9)],
+        ];
+
+        let mut dist = [u32::MAX; 6];
+        let mut heap = IndexedHeap::new();
+        heap.push(0, Reverse(0));
+
+        while let Some((u, Reverse(d))) = heap.pop() {
+            dist[u] = d;
+
+            for &(v, w) in edges[u] {
+                if dist[v] != u32::MAX {
+                    continue;
+                }
+
+                match heap.priority(&v) {
+                    Some(&Reverse(old)) if old <= d + w => {}
+                    _ => {
+                        heap.push(v, Reverse(d + w));
+                    }
+                }
+            }
+        }
+
+        assert_eq!(dist, [0, 7, 9, 20, 20, 11]);
+    }
+}
@@ -0,0 +1,65 @@
+pub mod binary_heap;
+pub mod dary;
+pub mod indexed;
+pub mod pairing;
+
+// The operations shared by the max-heaps in this module, so one can be
+// swapped for another.
+pub trait PriorityQueue<T: Ord> {
+    fn push(&mut self, value: T);
+    fn pop(&mut self) -> Option<T>;
+    fn peek(&self) -> Option<&T>;
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::binary_heap::BinaryHeap;
+    use super::dary::DaryHeap;
+    use super::pairing::PairingHeap;
+    use super::PriorityQueue;
+    use crate::rng::XorShift;
+
+    fn drain<Q: PriorityQueue<u64>>(mut queue: Q) -> Vec<u64> {
+        let mut rng = XorShift::new(18);
+        let mut pushed = Vec::new();
+        let mut popped = Vec::new();
+
+        for _ in 0..1000 {
+            let value = rng.next_below(100);
+            queue.push(value);
+            pushed.push(value);
+
+            if rng.next_below(3) == 0 {
+                let top = queue.peek().copied();
+                assert_eq!(queue.pop(), top);
+                popped.extend(top);
+            }
+        }
+
+        while let Some(value) = queue.pop() {
+            popped.push(value);
+        }
+
+        assert!(queue.is_empty());
+        pushed.sort();
+        let mut sorted = popped.clone();
+        sorted.sort();
+        assert_eq!(sorted, pushed);
+        popped
+    }
+
+    #[test]
+    fn implementations_agree() {
+        let expected = drain(BinaryHeap::new());
+
+        assert_eq!(drain(DaryHeap::<_, 2>::new()), expected);
+        assert_eq!(drain(DaryHeap::<_, 4>::new()), expected);
+        assert_eq!(drain(DaryHeap::<_, 7>::new()), expected);
+        assert_eq!(drain(PairingHeap::new()), expected);
+    }
+}
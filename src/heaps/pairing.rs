@@ -0,0 +1,184 @@
+use crate::heaps::PriorityQueue;
+
+// A heap-ordered multiway tree. Melding makes one root a child of the other,
+// and popping pairs up the root's children to find the next root.
+struct Node<T> {
+    value: T,
+    children: Vec<Box<Node<T>>>,
+}
+
+pub struct PairingHeap<T> {
+    root: Option<Box<Node<T>>>,
+    len: usize,
+}
+
+impl<T: Ord> PairingHeap<T> {
+    pub fn new() -> Self {
+        Self { root: None, len: 0 }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn peek(&self) -> Option<&T> {
+        self.root.as_ref().map(|root| &root.value)
+    }
+
+    pub fn push(&mut self, value: T) {
+        let node = Box::new(Node {
+            value,
+            children: Vec::new(),
+        });
+
+        self.root = Some(meld(self.root.take(), node));
+        self.len += 1;
+    }
+
+    pub fn pop(&mut self) -> Option<T> {
+        let root = self.root.take()?;
+        let Node { value, children } = *root;
+        self.root = merge_pairs(children);
+        self.len -= 1;
+        Some(value)
+    }
+
+    // Takes every element of `other` in O(1).
+    pub fn meld(&mut self, mut other: PairingHeap<T>) {
+        if let Some(root) = other.root.take() {
+            self.root = Some(meld(self.root.take(), root));
+            self.len += std::mem::take(&mut other.len);
+        }
+    }
+}
+
+impl<T: Ord> Default for PairingHeap<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Ord> FromIterator<T> for PairingHeap<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut heap = Self::new();
+
+        for value in iter {
+            heap.push(value);
+        }
+
+        heap
+    }
+}
+
+impl<T: Ord> PriorityQueue<T> for PairingHeap<T> {
+    fn push(&mut self, value: T) {
+        PairingHeap::push(self, value);
+    }
+
+    fn pop(&mut self) -> Option<T> {
+        PairingHeap::pop(self)
+    }
+
+    fn peek(&self) -> Option<&T> {
+        PairingHeap::peek(self)
+    }
+
+    fn len(&self) -> usize {
+        PairingHeap::len(self)
+    }
+}
+
+// A run of pushes leaves one root with n children, and a run of pops can
+// leave long chains, so free the nodes without recursion.
+impl<T> Drop for PairingHeap<T> {
+    fn drop(&mut self) {
+        let mut stack = Vec::from_iter(self.root.take());
+
+        while let Some(mut node) = stack.pop() {
+            stack.append(&mut node.children);
+        }
+    }
+}
+
+fn meld<T: Ord>(a: Option<Box<Node<T>>>, b: Box<Node<T>>) -> Box<Node<T>> {
+    let Some(a) = a else {
+        return b;
+    };
+
+    let (mut parent, child) = if a.value >= b.value { (a, b) } else { (b, a) };
+    parent.children.push(child);
+    parent
+}
+
+// The standard two-pass pairing: meld neighbours left to right, then meld
+// the results right to left into a single tree.
+fn merge_pairs<T: Ord>(children: Vec<Box<Node<T>>>) -> Option<Box<Node<T>>> {
+    let mut pairs = Vec::with_capacity(children.len().div_ceil(2));
+    let mut children = children.into_iter();
+
+    while let Some(first) = children.next() {
+        pairs.push(match children.next() {
+            Some(second) => meld(Some(first), second),
+            None => first,
+        });
+    }
+
+    pairs
+        .into_iter()
+        .rev()
+        .fold(None, |acc, tree| Some(meld(acc, tree)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn push_pop() {
+        let mut heap = PairingHeap::new();
+        assert_eq!(heap.pop(), None);
+
+        for value in [5, 2, 8, 1, 9, 3, 8] {
+            heap.push(value);
+        }
+
+        assert_eq!(heap.len(), 7);
+        assert_eq!(heap.peek(), Some(&9));
+
+        let popped: Vec<_> = std::iter::from_fn(|| heap.pop()).collect();
+        assert_eq!(popped, [9, 8, 8, 5, 3, 2, 1]);
+        assert!(heap.is_empty());
+    }
+
+    #[test]
+    fn meld() {
+        let mut a: PairingHeap<_> = (0..10).step_by(2).collect();
+        let b: PairingHeap<_> = (1..10).step_by(2).collect();
+
+        a.meld(b);
+        a.meld(PairingHeap::new());
+        assert_eq!(a.len(), 10);
+
+        let popped: Vec<_> = std::iter::from_fn(|| a.pop()).collect();
+        assert_eq!(popped, (0..10).rev().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn drop_wide_and_deep() {
+        // Descending pushes hang every node off the root, ascending ones
+        // build a single chain.
+        let mut wide: PairingHeap<_> = (0..100_000).rev().collect();
+        assert_eq!(wide.root.as_ref().unwrap().children.len(), 99_999);
+        assert_eq!(wide.pop(), Some(99_999));
+        assert_eq!(wide.pop(), Some(99_998));
+        drop(wide);
+
+        let deep: PairingHeap<_> = (0..100_000).collect();
+        assert_eq!(deep.root.as_ref().unwrap().children.len(), 1);
+        drop(deep);
+    }
+}
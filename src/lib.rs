@@ -1,3 +1,6 @@
+pub mod heaps;
 pub mod lists;
+pub mod range_query;
+pub mod rng;
 pub mod sort;
 pub mod trees;
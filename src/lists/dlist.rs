@@ -244,7 +244,7 @@ impl<'a, T> CursorMut<'a, T> {
                 }
             }
         } else {
-            std::mem::replace(self.list, DList::new())
+            std::mem::take(self.list)
         }
     }
 
@@ -282,7 +282,7 @@ impl<'a, T> CursorMut<'a, T> {
                 }
             }
         } else {
-            std::mem::replace(self.list, DList::new())
+            std::mem::take(self.list)
         }
     }
 
@@ -328,6 +328,12 @@ impl<'a, T> CursorMut<'a, T> {
     }
 }
 
+impl<T> Default for DList<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl<T> Drop for DList<T> {
     fn drop(&mut self) {
         while self.pop_head().is_some() {}
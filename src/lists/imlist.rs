@@ -21,6 +21,10 @@ impl<T> ImList<T> {
         self.len
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
     pub fn head(&self) -> Option<&T> {
         self.head.as_deref().map(|node| &node.value)
     }
@@ -66,6 +70,12 @@ impl<'a, T> Iterator for Iter<'a, T> {
     }
 }
 
+impl<T> Default for ImList<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl<T> Drop for ImList<T> {
     fn drop(&mut self) {
         let mut curr = self.head.take();
@@ -22,6 +22,10 @@ impl<T> List<T> {
         self.len
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
     pub fn peek(&self) -> Option<&T> {
         self.head.as_ref().map(|head| &head.value)
     }
@@ -2,5 +2,5 @@ pub mod dlist;
 pub mod imlist;
 pub mod list;
 pub mod que;
+pub mod skip_list;
 pub mod unque;
-// pub mod skip_list;
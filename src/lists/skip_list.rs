@@ -1,27 +1,475 @@
-use std::rc::Rc;
-use std::cell::RefCell;
+use std::marker::PhantomData;
+use std::ops::{Bound, RangeBounds};
+use std::ptr::NonNull;
 
-type Link = Option<Rc<RefCell<Node>>>;
+use crate::rng::XorShift;
 
-struct Node {
-    value: u64,
-    next: Vec<Link>,
+type Link<K, V> = Option<NonNull<Node<K, V>>>;
+
+const DEFAULT_MAX_LEVEL: usize = 16;
+const DEFAULT_P: f64 = 0.5;
+
+struct Node<K, V> {
+    key: K,
+    value: V,
+    next: Vec<Link<K, V>>,
 }
 
-pub struct SkipList {
-    head: Link,
-    tails: Vec<Link>,
+pub struct SkipList<K, V> {
+    head: Vec<Link<K, V>>,
     max_level: usize,
     len: usize,
+    p: f64,
+    rng: XorShift,
+    phantom: PhantomData<Box<Node<K, V>>>,
 }
 
-impl SkipList {
+impl<K: Ord, V> SkipList<K, V> {
     pub fn new() -> Self {
-        todo!()
+        Self::with_max_level(DEFAULT_MAX_LEVEL)
+    }
+
+    pub fn with_max_level(max_level: usize) -> Self {
+        Self::with_rng(XorShift::from_entropy(), max_level, DEFAULT_P)
+    }
+
+    pub fn with_seed(seed: u64, max_level: usize, p: f64) -> Self {
+        Self::with_rng(XorShift::new(seed), max_level, p)
+    }
+
+    fn with_rng(rng: XorShift, max_level: usize, p: f64) -> Self {
+        assert!(max_level > 0, "max_level = {}", max_level);
+        assert!(p > 0.0 && p < 1.0, "p = {}", p);
+
+        Self {
+            head: Vec::new(),
+            max_level,
+            len: 0,
+            p,
+            rng,
+            phantom: PhantomData,
+        }
     }
 
     pub fn len(&self) -> usize {
         self.len
     }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn max_level(&self) -> usize {
+        self.max_level
+    }
+
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let mut update = self.predecessors(&key);
+
+        if let Some(node) = self.next(update.first().copied().flatten(), 0) {
+            unsafe {
+                if (*node.as_ptr()).key == key {
+                    return Some(std::mem::replace(&mut (*node.as_ptr()).value, value));
+                }
+            }
+        }
+
+        let level = self.random_level();
+
+        while self.head.len() < level {
+            self.head.push(None);
+            update.push(None);
+        }
+
+        unsafe {
+            let mut node = NonNull::new_unchecked(Box::into_raw(Box::new(Node {
+                key,
+                value,
+                next: vec![None; level],
+            })));
+
+            for (i, &prev) in update.iter().enumerate().take(level) {
+                node.as_mut().next[i] = self.next(prev, i);
+                self.set_next(prev, i, Some(node));
+            }
+        }
+
+        self.len += 1;
+        None
+    }
+
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let update = self.predecessors(key);
+        let node = self.next(update.first().copied().flatten(), 0)?;
+
+        unsafe {
+            if (*node.as_ptr()).key != *key {
+                return None;
+            }
+
+            let boxed = Box::from_raw(node.as_ptr());
+
+            for (i, &next) in boxed.next.iter().enumerate() {
+                self.set_next(update[i], i, next);
+            }
+
+            while let Some(None) = self.head.last() {
+                self.head.pop();
+            }
+
+            self.len -= 1;
+            Some(boxed.value)
+        }
+    }
+
+    pub fn get(&self, key: &K) -> Option<&V> {
+        let node = self.seek(|k| k < key)?;
+
+        unsafe {
+            if (*node.as_ptr()).key == *key {
+                Some(&(*node.as_ptr()).value)
+            } else {
+                None
+            }
+        }
+    }
+
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        let node = self.seek(|k| k < key)?;
+
+        unsafe {
+            if (*node.as_ptr()).key == *key {
+                Some(&mut (*node.as_ptr()).value)
+            } else {
+                None
+            }
+        }
+    }
+
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.get(key).is_some()
+    }
+
+    pub fn first(&self) -> Option<(&K, &V)> {
+        let node = self.head.first().copied().flatten()?;
+        unsafe { Some((&(*node.as_ptr()).key, &(*node.as_ptr()).value)) }
+    }
+
+    pub fn last(&self) -> Option<(&K, &V)> {
+        let mut prev = None;
+
+        for level in (0..self.head.len()).rev() {
+            while let Some(next) = self.next(prev, level) {
+                prev = Some(next);
+            }
+        }
+
+        let node = prev?;
+        unsafe { Some((&(*node.as_ptr()).key, &(*node.as_ptr()).value)) }
+    }
+
+    pub fn range<R: RangeBounds<K>>(&self, range: R) -> Range<'_, K, V> {
+        let mut next = match range.start_bound() {
+            Bound::Included(start) => self.seek(|k| k < start),
+            Bound::Excluded(start) => self.seek(|k| k <= start),
+            Bound::Unbounded => self.head.first().copied().flatten(),
+        };
+
+        let end = match range.end_bound() {
+            Bound::Included(end) => self.seek(|k| k <= end),
+            Bound::Excluded(end) => self.seek(|k| k < end),
+            Bound::Unbounded => None,
+        };
+
+        if let Some(node) = next {
+            let key = unsafe { &(*node.as_ptr()).key };
+
+            let past_end = match range.end_bound() {
+                Bound::Included(end) => key > end,
+                Bound::Excluded(end) => key >= end,
+                Bound::Unbounded => false,
+            };
+
+            if past_end {
+                next = None;
+            }
+        }
+
+        Range {
+            next,
+            end,
+            phantom: PhantomData,
+        }
+    }
+
+    pub fn iter(&self) -> Range<'_, K, V> {
+        self.range(..)
+    }
+
+    // Tower height of every node, in key order.
+    pub fn levels(&self) -> Vec<usize> {
+        let mut levels = Vec::with_capacity(self.len);
+        let mut curr = self.head.first().copied().flatten();
+
+        while let Some(node) = curr {
+            unsafe {
+                levels.push(node.as_ref().next.len());
+                curr = node.as_ref().next[0];
+            }
+        }
+
+        levels
+    }
+
+    fn random_level(&mut self) -> usize {
+        let mut level = 1;
+
+        while level < self.max_level && self.rng.next_f64() < self.p {
+            level += 1;
+        }
+
+        level
+    }
+
+    // Returns the first node whose key does not satisfy `before`.
+    fn seek(&self, mut before: impl FnMut(&K) -> bool) -> Link<K, V> {
+        let mut prev = None;
+
+        for level in (0..self.head.len()).rev() {
+            while let Some(next) = self.next(prev, level) {
+                if before(unsafe { &(*next.as_ptr()).key }) {
+                    prev = Some(next);
+                } else {
+                    break;
+                }
+            }
+        }
+
+        self.next(prev, 0)
+    }
+
+    // Returns, for every level, the last node whose key is less than `key`.
+    // `None` stands for the head tower.
+    fn predecessors(&self, key: &K) -> Vec<Link<K, V>> {
+        let mut update = vec![None; self.head.len()];
+        let mut prev = None;
+
+        for level in (0..self.head.len()).rev() {
+            while let Some(next) = self.next(prev, level) {
+                if unsafe { &(*next.as_ptr()).key } < key {
+                    prev = Some(next);
+                } else {
+                    break;
+                }
+            }
+
+            update[level] = prev;
+        }
+
+        update
+    }
+}
+
+impl<K, V> SkipList<K, V> {
+    fn next(&self, prev: Link<K, V>, level: usize) -> Link<K, V> {
+        match prev {
+            Some(node) => unsafe { node.as_ref().next[level] },
+            None => self.head.get(level).copied().flatten(),
+        }
+    }
+
+    fn set_next(&mut self, prev: Link<K, V>, level: usize, link: Link<K, V>) {
+        match prev {
+            Some(mut node) => unsafe { node.as_mut().next[level] = link },
+            None => self.head[level] = link,
+        }
+    }
+}
+
+impl<K: Ord, V> Default for SkipList<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, V> Drop for SkipList<K, V> {
+    fn drop(&mut self) {
+        let mut curr = self.head.first().copied().flatten();
+
+        while let Some(node) = curr {
+            unsafe {
+                let boxed = Box::from_raw(node.as_ptr());
+                curr = boxed.next[0];
+            }
+        }
+    }
 }
 
+pub struct Range<'a, K, V> {
+    next: Link<K, V>,
+    end: Link<K, V>,
+    phantom: PhantomData<&'a Node<K, V>>,
+}
+
+impl<'a, K, V> Iterator for Range<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.next == self.end {
+            return None;
+        }
+
+        self.next.map(|node| unsafe {
+            self.next = node.as_ref().next[0];
+            (&(*node.as_ptr()).key, &(*node.as_ptr()).value)
+        })
+    }
+}
+
+impl<'a, K: Ord, V> IntoIterator for &'a SkipList<K, V> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Range<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn new_list() -> SkipList<u64, String> {
+        let mut list = SkipList::new();
+
+        for key in [5, 1, 9, 3, 7, 2, 8, 4, 6] {
+            list.insert(key, key.to_string());
+        }
+
+        list
+    }
+
+    #[test]
+    fn insert_get() {
+        let mut list = new_list();
+        assert_eq!(list.len(), 9);
+
+        assert_eq!(list.get(&3), Some(&"3".to_string()));
+        assert_eq!(list.get(&10), None);
+        assert!(list.contains_key(&9));
+        assert!(!list.contains_key(&0));
+
+        assert_eq!(list.insert(3, "three".to_string()), Some("3".to_string()));
+        assert_eq!(list.len(), 9);
+        assert_eq!(list.get(&3), Some(&"three".to_string()));
+
+        list.get_mut(&4).unwrap().push('!');
+        assert_eq!(list.get(&4), Some(&"4!".to_string()));
+        assert_eq!(list.get_mut(&0), None);
+    }
+
+    #[test]
+    fn remove() {
+        let mut list = new_list();
+
+        assert_eq!(list.remove(&5), Some("5".to_string()));
+        assert_eq!(list.remove(&5), None);
+        assert_eq!(list.remove(&0), None);
+        assert_eq!(list.len(), 8);
+
+        let keys: Vec<_> = list.iter().map(|(k, _)| *k).collect();
+        assert_eq!(keys, [1, 2, 3, 4, 6, 7, 8, 9]);
+
+        for key in 1..10 {
+            list.remove(&key);
+        }
+
+        assert!(list.is_empty());
+        assert_eq!(list.first(), None);
+        assert_eq!(list.last(), None);
+    }
+
+    #[test]
+    fn first_last() {
+        let list = new_list();
+        assert_eq!(list.first(), Some((&1, &"1".to_string())));
+        assert_eq!(list.last(), Some((&9, &"9".to_string())));
+    }
+
+    #[test]
+    fn range() {
+        let list = new_list();
+        let keys = |r: Range<'_, u64, String>| r.map(|(k, _)| *k).collect::<Vec<_>>();
+
+        assert_eq!(keys(list.range(3..6)), [3, 4, 5]);
+        assert_eq!(keys(list.range(3..=6)), [3, 4, 5, 6]);
+        assert_eq!(keys(list.range(..3)), [1, 2]);
+        assert_eq!(keys(list.range(7..)), [7, 8, 9]);
+        assert_eq!(keys(list.range(0..100)), [1, 2, 3, 4, 5, 6, 7, 8, 9]);
+        assert_eq!(keys(list.range(4..4)), []);
+        assert_eq!(
+            keys(list.range((Bound::Included(6), Bound::Excluded(3)))),
+            []
+        );
+        assert_eq!(keys(list.range(10..)), []);
+        assert_eq!(
+            keys(list.range((Bound::Excluded(2), Bound::Excluded(5)))),
+            [3, 4]
+        );
+    }
+
+    #[test]
+    fn seeded_layout_is_reproducible() {
+        let build = |seed| {
+            let mut list = SkipList::with_seed(seed, 12, 0.5);
+
+            for i in 0..500u64 {
+                list.insert((i * 7919) % 500, i);
+            }
+
+            for i in (0..500u64).step_by(3) {
+                list.remove(&i);
+            }
+
+            list.levels()
+        };
+
+        assert_eq!(build(42), build(42));
+        assert_ne!(build(42), build(43));
+        assert!(build(42).iter().all(|&level| (1..=12).contains(&level)));
+    }
+
+    #[test]
+    fn promotion_probability() {
+        let mut flat = SkipList::with_seed(1, 16, 0.01);
+        let mut tall = SkipList::with_seed(1, 16, 0.9);
+
+        for i in 0..1000u64 {
+            flat.insert(i, ());
+            tall.insert(i, ());
+        }
+
+        let sum = |list: &SkipList<u64, ()>| list.levels().iter().sum::<usize>();
+        assert!(sum(&flat) < 1100);
+        assert!(sum(&tall) > 5000);
+    }
+
+    #[test]
+    fn many() {
+        let mut list = SkipList::new();
+
+        for i in 0..1000u64 {
+            list.insert((i * 7919) % 1000, i);
+        }
+
+        assert_eq!(list.len(), 1000);
+        assert!(list.iter().map(|(k, _)| *k).eq(0..1000));
+
+        for i in (0..1000u64).step_by(2) {
+            assert!(list.remove(&i).is_some());
+        }
+
+        assert_eq!(list.len(), 500);
+        assert!(list.iter().map(|(k, _)| *k).eq((1..1000).step_by(2)));
+    }
+}
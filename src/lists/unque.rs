@@ -63,6 +63,12 @@ impl<T> Que<T> {
     }
 }
 
+impl<T> Default for Que<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl<T> Drop for Que<T> {
     fn drop(&mut self) {
         while self.deque().is_some() {}
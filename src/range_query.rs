@@ -0,0 +1,568 @@
+use std::marker::PhantomData;
+use std::ops::{Add, Bound, RangeBounds, Sub};
+
+// An associative operation with an identity, plus the range updates it
+// supports: how a pending `Delta` changes the aggregate of a segment of
+// `len` elements, and how a newer delta composes with a pending one.
+// Operations that only take point updates can use `()` as their delta.
+pub trait Op<T> {
+    type Delta: Clone;
+
+    fn identity() -> T;
+    fn combine(a: &T, b: &T) -> T;
+    fn apply(agg: &T, delta: &Self::Delta, len: usize) -> T;
+    fn compose(pending: &Self::Delta, delta: &Self::Delta) -> Self::Delta;
+}
+
+pub struct Sum;
+pub struct Min;
+pub struct Max;
+
+macro_rules! impl_ops {
+    ($min:ident, $max:ident; $($t:ty),*) => {$(
+        impl Op<$t> for Sum {
+            type Delta = $t;
+
+            fn identity() -> $t {
+                0 as $t
+            }
+
+            fn combine(a: &$t, b: &$t) -> $t {
+                a + b
+            }
+
+            fn apply(agg: &$t, delta: &$t, len: usize) -> $t {
+                agg + delta * len as $t
+            }
+
+            fn compose(pending: &$t, delta: &$t) -> $t {
+                pending + delta
+            }
+        }
+
+        impl Op<$t> for Min {
+            type Delta = $t;
+
+            fn identity() -> $t {
+                <$t>::$max
+            }
+
+            fn combine(a: &$t, b: &$t) -> $t {
+                if b < a { *b } else { *a }
+            }
+
+            fn apply(agg: &$t, delta: &$t, _: usize) -> $t {
+                agg + delta
+            }
+
+            fn compose(pending: &$t, delta: &$t) -> $t {
+                pending + delta
+            }
+        }
+
+        impl Op<$t> for Max {
+            type Delta = $t;
+
+            fn identity() -> $t {
+                <$t>::$min
+            }
+
+            fn combine(a: &$t, b: &$t) -> $t {
+                if b > a { *b } else { *a }
+            }
+
+            fn apply(agg: &$t, delta: &$t, _: usize) -> $t {
+                agg + delta
+            }
+
+            fn compose(pending: &$t, delta: &$t) -> $t {
+                pending + delta
+            }
+        }
+    )*};
+}
+
+impl_ops!(MIN, MAX; i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
+impl_ops!(NEG_INFINITY, INFINITY; f32, f64);
+
+// Resolves `range` against a length into a half-open `(start, end)`.
+fn bounds(range: impl RangeBounds<usize>, len: usize) -> (usize, usize) {
+    let start = match range.start_bound() {
+        Bound::Included(&i) => i,
+        Bound::Excluded(&i) => i + 1,
+        Bound::Unbounded => 0,
+    };
+
+    let end = match range.end_bound() {
+        Bound::Included(&i) => i + 1,
+        Bound::Excluded(&i) => i,
+        Bound::Unbounded => len,
+    };
+
+    assert!(start <= end, "range start is greater than its end");
+    assert!(end <= len, "range end out of bounds");
+    (start, end)
+}
+
+// Node 1 covers the whole slice and node i has children 2i and 2i + 1. A
+// node's pending delta has already been applied to its own aggregate but not
+// yet to its children's.
+pub struct SegmentTree<T, O: Op<T>> {
+    len: usize,
+    tree: Vec<T>,
+    lazy: Vec<Option<O::Delta>>,
+    op: PhantomData<O>,
+}
+
+impl<T: Clone, O: Op<T>> SegmentTree<T, O> {
+    pub fn new(values: &[T]) -> Self {
+        let len = values.len();
+        let mut tree = Self {
+            len,
+            tree: vec![O::identity(); 4 * len.max(1)],
+            lazy: vec![None; 4 * len.max(1)],
+            op: PhantomData,
+        };
+
+        if len > 0 {
+            tree.build(1, 0, len, values);
+        }
+
+        tree
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn set(&mut self, i: usize, value: T) {
+        assert!(i < self.len, "index out of bounds");
+        self.set_in(1, 0, self.len, i, value);
+    }
+
+    // The aggregate of the elements in `range`, or the identity if it is
+    // empty.
+    pub fn query(&self, range: impl RangeBounds<usize>) -> T {
+        let (start, end) = bounds(range, self.len);
+
+        if start == end {
+            return O::identity();
+        }
+
+        self.query_in(1, 0, self.len, start, end, None)
+    }
+
+    // Applies `delta` to every element in `range`.
+    pub fn add(&mut self, range: impl RangeBounds<usize>, delta: O::Delta) {
+        let (start, end) = bounds(range, self.len);
+
+        if start < end {
+            self.add_in(1, 0, self.len, start, end, &delta);
+        }
+    }
+
+    fn build(&mut self, node: usize, lo: usize, hi: usize, values: &[T]) {
+        if hi - lo == 1 {
+            self.tree[node] = values[lo].clone();
+            return;
+        }
+
+        let mid = lo + (hi - lo) / 2;
+        self.build(2 * node, lo, mid, values);
+        self.build(2 * node + 1, mid, hi, values);
+        self.pull(node);
+    }
+
+    fn pull(&mut self, node: usize) {
+        self.tree[node] = O::combine(&self.tree[2 * node], &self.tree[2 * node + 1]);
+    }
+
+    fn push(&mut self, node: usize, lo: usize, hi: usize) {
+        if let Some(delta) = self.lazy[node].take() {
+            let mid = lo + (hi - lo) / 2;
+            self.apply(2 * node, mid - lo, &delta);
+            self.apply(2 * node + 1, hi - mid, &delta);
+        }
+    }
+
+    fn apply(&mut self, node: usize, len: usize, delta: &O::Delta) {
+        self.tree[node] = O::apply(&self.tree[node], delta, len);
+
+        self.lazy[node] = Some(match self.lazy[node].take() {
+            Some(pending) => O::compose(&pending, delta),
+            None => delta.clone(),
+        });
+    }
+
+    fn set_in(&mut self, node: usize, lo: usize, hi: usize, i: usize, value: T) {
+        if hi - lo == 1 {
+            self.tree[node] = value;
+            return;
+        }
+
+        self.push(node, lo, hi);
+        let mid = lo + (hi - lo) / 2;
+
+        if i < mid {
+            self.set_in(2 * node, lo, mid, i, value);
+        } else {
+            self.set_in(2 * node + 1, mid, hi, i, value);
+        }
+
+        self.pull(node);
+    }
+
+    // `carry` is what the ancestors of `node` still owe it: their pending
+    // deltas composed, the nearest (and oldest) first. Carrying them down
+    // instead of pushing them leaves the tree untouched.
+    fn query_in(
+        &self,
+        node: usize,
+        lo: usize,
+        hi: usize,
+        start: usize,
+        end: usize,
+        carry: Option<&O::Delta>,
+    ) -> T {
+        if start <= lo && hi <= end {
+            return match carry {
+                Some(delta) => O::apply(&self.tree[node], delta, hi - lo),
+                None => self.tree[node].clone(),
+            };
+        }
+
+        let carry = match (&self.lazy[node], carry) {
+            (Some(pending), Some(delta)) => Some(O::compose(pending, delta)),
+            (pending, delta) => pending.as_ref().or(delta).cloned(),
+        };
+
+        let carry = carry.as_ref();
+        let mid = lo + (hi - lo) / 2;
+
+        match (start < mid, mid < end) {
+            (true, true) => O::combine(
+                &self.query_in(2 * node, lo, mid, start, end, carry),
+                &self.query_in(2 * node + 1, mid, hi, start, end, carry),
+            ),
+            (true, false) => self.query_in(2 * node, lo, mid, start, end, carry),
+            _ => self.query_in(2 * node + 1, mid, hi, start, end, carry),
+        }
+    }
+
+    fn add_in(
+        &mut self,
+        node: usize,
+        lo: usize,
+        hi: usize,
+        start: usize,
+        end: usize,
+        delta: &O::Delta,
+    ) {
+        if start <= lo && hi <= end {
+            self.apply(node, hi - lo, delta);
+            return;
+        }
+
+        self.push(node, lo, hi);
+        let mid = lo + (hi - lo) / 2;
+
+        if start < mid {
+            self.add_in(2 * node, lo, mid, start, end, delta);
+        }
+
+        if mid < end {
+            self.add_in(2 * node + 1, mid, hi, start, end, delta);
+        }
+
+        self.pull(node);
+    }
+}
+
+// Binary indexed tree. `tree[i - 1]` holds the sum of the elements in
+// `i - (i & -i)..i`, where `i & -i` is the lowest set bit of `i`.
+pub struct FenwickTree<T> {
+    tree: Vec<T>,
+}
+
+impl<T: Copy + Default + Add<Output = T> + Sub<Output = T>> FenwickTree<T> {
+    pub fn new(len: usize) -> Self {
+        Self {
+            tree: vec![T::default(); len],
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.tree.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tree.is_empty()
+    }
+
+    pub fn add(&mut self, i: usize, delta: T) {
+        assert!(i < self.len(), "index out of bounds");
+        let mut i = i + 1;
+
+        while i <= self.len() {
+            self.tree[i - 1] = self.tree[i - 1] + delta;
+            i += i & i.wrapping_neg();
+        }
+    }
+
+    // The sum of the first `end` elements.
+    pub fn prefix_sum(&self, end: usize) -> T {
+        assert!(end <= self.len(), "index out of bounds");
+        let mut sum = T::default();
+        let mut i = end;
+
+        while i > 0 {
+            sum = sum + self.tree[i - 1];
+            i -= i & i.wrapping_neg();
+        }
+
+        sum
+    }
+
+    pub fn range_sum(&self, range: impl RangeBounds<usize>) -> T {
+        let (start, end) = bounds(range, self.len());
+        self.prefix_sum(end) - self.prefix_sum(start)
+    }
+}
+
+impl<T: Copy + Default + Add<Output = T> + Sub<Output = T> + PartialOrd> FenwickTree<T> {
+    // The smallest `i` such that the elements up to and including `i` sum to
+    // at least `target`, or `None` if even the full sum falls short. Assumes
+    // no element is negative.
+    pub fn lower_bound(&self, target: T) -> Option<usize> {
+        let mut pos = 0;
+        let mut sum = T::default();
+        let mut step = self.len().checked_next_power_of_two()?;
+
+        while step > 0 {
+            if pos + step <= self.len() && sum + self.tree[pos + step - 1] < target {
+                pos += step;
+                sum = sum + self.tree[pos - 1];
+            }
+
+            step /= 2;
+        }
+
+        (pos < self.len()).then_some(pos)
+    }
+}
+
+impl<T: Copy + Default + Add<Output = T> + Sub<Output = T>> From<&[T]> for FenwickTree<T> {
+    fn from(values: &[T]) -> Self {
+        let mut tree = values.to_vec();
+
+        for i in 1..=tree.len() {
+            let parent = i + (i & i.wrapping_neg());
+
+            if parent <= tree.len() {
+                tree[parent - 1] = tree[parent - 1] + tree[i - 1];
+            }
+        }
+
+        Self { tree }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::XorShift;
+
+    fn check<O: Op<i64, Delta = i64>>(fold: fn(&[i64]) -> i64) {
+        let mut rng = XorShift::new(15);
+        let mut values: Vec<i64> = (0..200).map(|_| rng.next_below(1000) as i64).collect();
+        let mut tree = SegmentTree::<i64, O>::new(&values);
+
+        for _ in 0..2000 {
+            let a = rng.next_below(201) as usize;
+            let b = rng.next_below(201) as usize;
+            let (start, end) = (a.min(b), a.max(b));
+
+            match rng.next_below(3) {
+                0 => {
+                    let i = rng.next_below(200) as usize;
+                    let value = rng.next_below(1000) as i64 - 500;
+                    values[i] = value;
+                    tree.set(i, value);
+                }
+                1 => {
+                    let delta = rng.next_below(100) as i64 - 50;
+                    values[start..end].iter_mut().for_each(|v| *v += delta);
+                    tree.add(start..end, delta);
+                }
+                _ => assert_eq!(tree.query(start..end), fold(&values[start..end])),
+            }
+        }
+    }
+
+    #[test]
+    fn segment_tree_sum() {
+        check::<Sum>(|s| s.iter().sum());
+    }
+
+    #[test]
+    fn segment_tree_min() {
+        check::<Min>(|s| s.iter().copied().min().unwrap_or(i64::MAX));
+    }
+
+    #[test]
+    fn segment_tree_max() {
+        check::<Max>(|s| s.iter().copied().max().unwrap_or(i64::MIN));
+    }
+
+    #[test]
+    fn rolling_window() {
+        let latencies = [12.0, 15.5, 9.0, 30.0, 11.0, 14.0, 8.5];
+        let max = SegmentTree::<f64, Max>::new(&latencies);
+        let mut sum = SegmentTree::<f64, Sum>::new(&latencies);
+
+        let windows: Vec<f64> = (0..=latencies.len() - 3)
+            .map(|i| max.query(i..i + 3))
+            .collect();
+        assert_eq!(windows, [15.5, 30.0, 30.0, 30.0, 14.0]);
+        assert_eq!(sum.query(..), 100.0);
+        assert_eq!(sum.query(2..=3), 39.0);
+
+        sum.add(.., 1.0);
+        assert_eq!(sum.query(..), 107.0);
+
+        let empty = SegmentTree::<f64, Min>::new(&[]);
+        assert!(empty.is_empty());
+        assert_eq!(empty.query(..), f64::INFINITY);
+    }
+
+    struct Gcd;
+
+    impl Op<u64> for Gcd {
+        type Delta = ();
+
+        fn identity() -> u64 {
+            0
+        }
+
+        fn combine(a: &u64, b: &u64) -> u64 {
+            let (mut a, mut b) = (*a, *b);
+
+            while b != 0 {
+                (a, b) = (b, a % b);
+            }
+
+            a
+        }
+
+        fn apply(agg: &u64, _: &(), _: usize) -> u64 {
+            *agg
+        }
+
+        fn compose(_: &(), _: &()) {}
+    }
+
+    // The minimum and how many times it occurs, with range adds that shift
+    // the minimum but keep its count.
+    struct MinCount;
+
+    impl Op<(i32, usize)> for MinCount {
+        type Delta = i32;
+
+        fn identity() -> (i32, usize) {
+            (i32::MAX, 0)
+        }
+
+        fn combine(a: &(i32, usize), b: &(i32, usize)) -> (i32, usize) {
+            match a.0.cmp(&b.0) {
+                std::cmp::Ordering::Less => *a,
+                std::cmp::Ordering::Greater => *b,
+                std::cmp::Ordering::Equal => (a.0, a.1 + b.1),
+            }
+        }
+
+        fn apply(agg: &(i32, usize), delta: &i32, _: usize) -> (i32, usize) {
+            (agg.0 + delta, agg.1)
+        }
+
+        fn compose(pending: &i32, delta: &i32) -> i32 {
+            pending + delta
+        }
+    }
+
+    #[test]
+    fn custom_ops() {
+        let mut gcd = SegmentTree::<u64, Gcd>::new(&[12, 18, 24, 7, 35, 14]);
+        assert_eq!(gcd.query(..3), 6);
+        assert_eq!(gcd.query(3..), 7);
+        assert_eq!(gcd.query(..), 1);
+
+        gcd.set(3, 6);
+        assert_eq!(gcd.query(..4), 6);
+
+        let values: Vec<(i32, usize)> = [3, 1, 4, 1, 5, 1].iter().map(|&v| (v, 1)).collect();
+        let mut min = SegmentTree::<(i32, usize), MinCount>::new(&values);
+        assert_eq!(min.query(..), (1, 3));
+
+        min.add(..2, 10);
+        assert_eq!(min.query(..), (1, 2));
+        assert_eq!(min.query(..2), (11, 1));
+
+        min.add(2.., 10);
+        assert_eq!(min.query(..), (11, 3));
+    }
+
+    #[test]
+    fn query_through_shared_references() {
+        let mut tree = SegmentTree::<i64, Sum>::new(&[1, 2, 3, 4, 5, 6, 7, 8]);
+        tree.add(..6, 10);
+        tree.add(2..4, -1);
+
+        // Pending deltas are carried down, not pushed, so the tree can be
+        // read from several places at once.
+        let (a, b) = (&tree, &tree);
+        assert_eq!(a.query(3..4), 13);
+        assert_eq!(b.query(1..7), 2 + 3 + 4 + 5 + 6 + 7 + 50 - 2);
+        assert_eq!(a.query(..), 36 + 60 - 2);
+    }
+
+    #[test]
+    fn fenwick_tree() {
+        let values = [3u32, 0, 4, 1, 5, 9, 2, 6];
+        let mut tree = FenwickTree::from(&values[..]);
+        let mut brute = FenwickTree::new(values.len());
+
+        for (i, &value) in values.iter().enumerate() {
+            brute.add(i, value);
+        }
+
+        for end in 0..=values.len() {
+            let sum: u32 = values[..end].iter().sum();
+            assert_eq!(tree.prefix_sum(end), sum);
+            assert_eq!(brute.prefix_sum(end), sum);
+        }
+
+        assert_eq!(tree.range_sum(2..5), 10);
+        assert_eq!(tree.range_sum(..), 30);
+
+        tree.add(1, 2);
+        assert_eq!(tree.range_sum(1..=1), 2);
+    }
+
+    #[test]
+    fn fenwick_lower_bound() {
+        let tree = FenwickTree::from(&[3u32, 0, 4, 1, 5, 9, 2][..]);
+
+        assert_eq!(tree.lower_bound(0), Some(0));
+        assert_eq!(tree.lower_bound(3), Some(0));
+        assert_eq!(tree.lower_bound(4), Some(2));
+        assert_eq!(tree.lower_bound(8), Some(3));
+        assert_eq!(tree.lower_bound(9), Some(4));
+        assert_eq!(tree.lower_bound(24), Some(6));
+        assert_eq!(tree.lower_bound(25), None);
+        assert_eq!(FenwickTree::<u32>::new(0).lower_bound(1), None);
+    }
+}
@@ -1,26 +1,67 @@
+use std::cmp::Ordering;
+
 pub fn bubble_sort<T: Ord>(slice: &mut [T]) {
+    bubble_sort_by(slice, T::cmp);
+}
+
+pub fn bubble_sort_by<T, F>(slice: &mut [T], mut compare: F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
     for i in 0..slice.len() {
         for j in (i + 1..slice.len()).rev() {
-            if slice[j] < slice[j - 1] {
+            if compare(&slice[j], &slice[j - 1]) == Ordering::Less {
                 slice.swap(j, j - 1);
             }
         }
     }
 }
 
+pub fn bubble_sort_by_key<T, K, F>(slice: &mut [T], mut f: F)
+where
+    K: Ord,
+    F: FnMut(&T) -> K,
+{
+    bubble_sort_by(slice, |a, b| f(a).cmp(&f(b)));
+}
+
+pub fn bubble_sort_by_cached_key<T, K, F>(slice: &mut [T], f: F)
+where
+    K: Ord,
+    F: FnMut(&T) -> K,
+{
+    super::sort_by_cached_key(slice, f, |keys| bubble_sort_by(keys, |a, b| a.0.cmp(&b.0)));
+}
+
 pub fn bubble_sort2<T: Ord>(slice: &mut [T]) {
+    bubble_sort2_by(slice, T::cmp);
+}
+
+pub fn bubble_sort2_by<T, F>(slice: &mut [T], mut compare: F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
     for i in 0..slice.len() {
         for j in 0..(slice.len() - (i + 1)) {
-            if slice[j] > slice[j + 1] {
+            if compare(&slice[j], &slice[j + 1]) == Ordering::Greater {
                 slice.swap(j, j + 1);
             }
         }
     }
 }
 
+pub fn bubble_sort2_by_key<T, K, F>(slice: &mut [T], mut f: F)
+where
+    K: Ord,
+    F: FnMut(&T) -> K,
+{
+    bubble_sort2_by(slice, |a, b| f(a).cmp(&f(b)));
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::sort::tests::records;
 
     #[test]
     fn bubble_sort_works() {
@@ -53,4 +94,31 @@ mod tests {
         bubble_sort2(&mut arr);
         assert_eq!(arr, [1, 2, 3, 4, 5, 6]);
     }
+
+    #[test]
+    fn bubble_sort_by_works() {
+        let mut expected = records();
+        expected.sort_by_key(|r| r.1);
+
+        let mut arr = records();
+        bubble_sort_by_key(&mut arr, |r| r.1);
+        assert_eq!(arr, expected);
+
+        let mut arr = records();
+        bubble_sort_by_cached_key(&mut arr, |r| r.1);
+        assert_eq!(arr, expected);
+
+        let mut arr = records();
+        bubble_sort2_by_key(&mut arr, |r| r.1);
+        assert_eq!(arr, expected);
+
+        expected.sort_by_key(|r| std::cmp::Reverse(r.1));
+        let mut arr = records();
+        bubble_sort_by(&mut arr, |a, b| b.1.cmp(&a.1));
+        assert_eq!(arr, expected);
+
+        let mut arr = records();
+        bubble_sort2_by(&mut arr, |a, b| b.1.cmp(&a.1));
+        assert_eq!(arr, expected);
+    }
 }
@@ -1,60 +1,266 @@
-#![allow(unused, warnings)]
+use std::cmp::Ordering;
 
-use std::fmt::Debug;
-
-fn parent(i: usize) -> usize {
+pub(crate) fn parent(i: usize) -> usize {
     (i - 1) >> 1
 }
 
-fn left(i: usize) -> usize {
+pub(crate) fn left(i: usize) -> usize {
     (i << 1) + 1
 }
 
-fn right(i: usize) -> usize {
+pub(crate) fn right(i: usize) -> usize {
     (i << 1) + 2
 }
 
-pub fn max_heapify<T: Ord + Debug>(arr: &mut [T], i: usize, heapsize: usize) {
-    let left = left(i);
-    let right = right(i);
+pub(crate) fn parent_dary(i: usize, d: usize) -> usize {
+    (i - 1) / d
+}
 
-    let mut largest = 0;
+pub(crate) fn child_dary(i: usize, d: usize, k: usize) -> usize {
+    d * i + k + 1
+}
 
-    if left < heapsize && arr[left] > arr[i] {
-        largest = left;
-    } else {
-        largest = i;
-    }
+pub fn max_heapify<T: Ord>(arr: &mut [T], i: usize, heapsize: usize) {
+    max_heapify_by(arr, i, heapsize, &mut T::cmp);
+}
 
-    if right < heapsize && arr[right] > arr[largest] {
-        largest = right;
-    }
+// Sifts `arr[i]` down until neither child compares greater under `compare`.
+pub fn max_heapify_by<T, F>(arr: &mut [T], mut i: usize, heapsize: usize, compare: &mut F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    loop {
+        let left = left(i);
+        let right = right(i);
+        let mut largest = i;
+
+        if left < heapsize && compare(&arr[left], &arr[largest]) == Ordering::Greater {
+            largest = left;
+        }
+
+        if right < heapsize && compare(&arr[right], &arr[largest]) == Ordering::Greater {
+            largest = right;
+        }
+
+        if largest == i {
+            return;
+        }
 
-    if largest != i {
         arr.swap(i, largest);
-        max_heapify(arr, largest, heapsize);
+        i = largest;
     }
 }
 
-pub fn build_max_heap<T: Ord + Debug>(arr: &mut [T]) {
+pub fn build_max_heap<T: Ord>(arr: &mut [T]) {
+    build_max_heap_by(arr, T::cmp);
+}
+
+pub fn build_max_heap_by<T, F>(arr: &mut [T], mut compare: F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
     let n = arr.len() / 2;
 
     for i in (0..n).rev() {
-        max_heapify(arr, i, arr.len());
+        max_heapify_by(arr, i, arr.len(), &mut compare);
+    }
+}
+
+pub fn build_max_heap_by_key<T, K, F>(arr: &mut [T], mut f: F)
+where
+    K: Ord,
+    F: FnMut(&T) -> K,
+{
+    build_max_heap_by(arr, |a, b| f(a).cmp(&f(b)));
+}
+
+pub fn min_heapify<T: Ord>(arr: &mut [T], i: usize, heapsize: usize) {
+    min_heapify_by(arr, i, heapsize, &mut T::cmp);
+}
+
+// Sifts `arr[i]` down until neither child compares less under `compare`.
+pub fn min_heapify_by<T, F>(arr: &mut [T], i: usize, heapsize: usize, compare: &mut F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    max_heapify_by(arr, i, heapsize, &mut |a, b| compare(b, a));
+}
+
+pub fn build_min_heap<T: Ord>(arr: &mut [T]) {
+    build_min_heap_by(arr, T::cmp);
+}
+
+pub fn build_min_heap_by<T, F>(arr: &mut [T], mut compare: F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    build_max_heap_by(arr, |a, b| compare(b, a));
+}
+
+pub fn build_min_heap_by_key<T, K, F>(arr: &mut [T], mut f: F)
+where
+    K: Ord,
+    F: FnMut(&T) -> K,
+{
+    build_min_heap_by(arr, |a, b| f(a).cmp(&f(b)));
+}
+
+pub fn heap_sort<T: Ord>(arr: &mut [T]) {
+    heap_sort_by(arr, T::cmp);
+}
+
+pub fn heap_sort_by<T, F>(arr: &mut [T], mut compare: F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    build_max_heap_by(arr, &mut compare);
+    let mut heapsize = arr.len();
+
+    for i in (1..arr.len()).rev() {
+        arr.swap(0, i);
+        heapsize -= 1;
+        max_heapify_by(arr, 0, heapsize, &mut compare);
+    }
+}
+
+pub fn heap_sort_by_key<T, K, F>(arr: &mut [T], mut f: F)
+where
+    K: Ord,
+    F: FnMut(&T) -> K,
+{
+    heap_sort_by(arr, |a, b| f(a).cmp(&f(b)));
+}
+
+// Moves the `k` largest elements to the front of `arr`, in no particular
+// order, and returns them. Keeps them in a min-heap of size `k` whose root is
+// the one to evict, so it runs in O(n log k).
+pub fn heap_select_k<T: Ord>(arr: &mut [T], k: usize) -> &mut [T] {
+    heap_select_k_by(arr, k, T::cmp)
+}
+
+pub fn heap_select_k_by<T, F>(arr: &mut [T], k: usize, mut compare: F) -> &mut [T]
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    let k = k.min(arr.len());
+
+    if k == 0 {
+        return &mut arr[..0];
+    }
+
+    build_min_heap_by(&mut arr[..k], &mut compare);
+
+    for i in k..arr.len() {
+        if compare(&arr[i], &arr[0]) == Ordering::Greater {
+            arr.swap(0, i);
+            min_heapify_by(arr, 0, k, &mut compare);
+        }
+    }
+
+    &mut arr[..k]
+}
+
+pub fn heap_select_k_by_key<T, K, F>(arr: &mut [T], k: usize, mut f: F) -> &mut [T]
+where
+    K: Ord,
+    F: FnMut(&T) -> K,
+{
+    heap_select_k_by(arr, k, |a, b| f(a).cmp(&f(b)))
+}
+
+pub fn max_heapify_dary<T: Ord>(arr: &mut [T], d: usize, i: usize, heapsize: usize) {
+    max_heapify_dary_by(arr, d, i, heapsize, &mut T::cmp);
+}
+
+// The same as `max_heapify_by`, for a heap where each node has `d` children.
+pub fn max_heapify_dary_by<T, F>(
+    arr: &mut [T],
+    d: usize,
+    mut i: usize,
+    heapsize: usize,
+    compare: &mut F,
+) where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    loop {
+        let mut largest = i;
+
+        for k in 0..d {
+            let child = child_dary(i, d, k);
+
+            if child >= heapsize {
+                break;
+            }
+
+            if compare(&arr[child], &arr[largest]) == Ordering::Greater {
+                largest = child;
+            }
+        }
+
+        if largest == i {
+            return;
+        }
+
+        arr.swap(i, largest);
+        i = largest;
+    }
+}
+
+pub fn build_max_heap_dary<T: Ord>(arr: &mut [T], d: usize) {
+    build_max_heap_dary_by(arr, d, T::cmp);
+}
+
+pub fn build_max_heap_dary_by<T, F>(arr: &mut [T], d: usize, mut compare: F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    assert!(d >= 2, "a heap needs at least two children per node");
+
+    if arr.len() < 2 {
+        return;
+    }
+
+    for i in (0..=parent_dary(arr.len() - 1, d)).rev() {
+        max_heapify_dary_by(arr, d, i, arr.len(), &mut compare);
     }
 }
 
-pub fn heap_sort<T: Ord + Debug>(arr: &mut [T]) {
-    build_max_heap(arr);
+pub fn build_max_heap_dary_by_key<T, K, F>(arr: &mut [T], d: usize, mut f: F)
+where
+    K: Ord,
+    F: FnMut(&T) -> K,
+{
+    build_max_heap_dary_by(arr, d, |a, b| f(a).cmp(&f(b)));
+}
+
+// Heap sort on a `d`-ary heap. Wider nodes make the heap shallower at the
+// cost of more comparisons per level.
+pub fn heap_sort_dary<T: Ord>(arr: &mut [T], d: usize) {
+    heap_sort_dary_by(arr, d, T::cmp);
+}
+
+pub fn heap_sort_dary_by<T, F>(arr: &mut [T], d: usize, mut compare: F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    build_max_heap_dary_by(arr, d, &mut compare);
     let mut heapsize = arr.len();
 
     for i in (1..arr.len()).rev() {
         arr.swap(0, i);
         heapsize -= 1;
-        max_heapify(arr, 0, heapsize);
+        max_heapify_dary_by(arr, d, 0, heapsize, &mut compare);
     }
 }
 
+pub fn heap_sort_dary_by_key<T, K, F>(arr: &mut [T], d: usize, mut f: F)
+where
+    K: Ord,
+    F: FnMut(&T) -> K,
+{
+    heap_sort_dary_by(arr, d, |a, b| f(a).cmp(&f(b)));
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -110,4 +316,119 @@ mod tests {
         heap_sort(&mut arr);
         assert_eq!(arr, [1, 2, 3, 4, 5, 6]);
     }
+
+    #[test]
+    fn heap_sort_dary_works() {
+        let mut rng = crate::rng::XorShift::new(18);
+        let arr: Vec<u64> = (0..500).map(|_| rng.next_below(100)).collect();
+        let mut expected = arr.clone();
+        expected.sort();
+
+        for d in 2..=8 {
+            let mut arr = arr.clone();
+            heap_sort_dary(&mut arr, d);
+            assert_eq!(arr, expected);
+        }
+
+        let mut arr0: [u8; 0] = [];
+        heap_sort_dary(&mut arr0, 4);
+
+        let mut arr1 = [1];
+        heap_sort_dary(&mut arr1, 4);
+        assert_eq!(arr1, [1]);
+
+        let mut arr = jobs();
+        heap_sort_dary_by_key(&mut arr, 3, |job| job.priority);
+        let names: Vec<_> = arr.iter().map(|job| job.name).collect();
+        assert_eq!(names, ["lint", "docs", "build", "test", "deploy"]);
+
+        heap_sort_dary_by(&mut arr, 4, |a, b| b.name.cmp(a.name));
+        let names: Vec<_> = arr.iter().map(|job| job.name).collect();
+        assert_eq!(names, ["test", "lint", "docs", "deploy", "build"]);
+    }
+
+    #[test]
+    fn dary_indices() {
+        assert_eq!(child_dary(0, 2, 1), right(0));
+        assert_eq!(child_dary(3, 2, 0), left(3));
+        assert_eq!(parent_dary(8, 2), parent(8));
+
+        assert_eq!(child_dary(1, 4, 0), 5);
+        assert_eq!(child_dary(1, 4, 3), 8);
+        assert_eq!(parent_dary(8, 4), 1);
+        assert_eq!(parent_dary(9, 4), 2);
+    }
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Job {
+        name: &'static str,
+        priority: u32,
+    }
+
+    fn jobs() -> Vec<Job> {
+        [
+            ("build", 3),
+            ("test", 5),
+            ("lint", 1),
+            ("deploy", 9),
+            ("docs", 2),
+        ]
+        .into_iter()
+        .map(|(name, priority)| Job { name, priority })
+        .collect()
+    }
+
+    #[test]
+    fn heap_sort_by_field() {
+        let mut arr = jobs();
+        heap_sort_by_key(&mut arr, |job| job.priority);
+
+        let names: Vec<_> = arr.iter().map(|job| job.name).collect();
+        assert_eq!(names, ["lint", "docs", "build", "test", "deploy"]);
+
+        heap_sort_by(&mut arr, |a, b| b.name.cmp(a.name));
+        let names: Vec<_> = arr.iter().map(|job| job.name).collect();
+        assert_eq!(names, ["test", "lint", "docs", "deploy", "build"]);
+    }
+
+    #[test]
+    fn min_heap_works() {
+        let mut arr = [4, 1, 3, 2, 16, 9, 10, 14, 8, 7];
+        build_min_heap(&mut arr);
+        assert!((1..arr.len()).all(|i| arr[parent(i)] <= arr[i]));
+
+        arr[0] = 20;
+        min_heapify(&mut arr, 0, 10);
+        assert!((1..arr.len()).all(|i| arr[parent(i)] <= arr[i]));
+        assert_eq!(arr[0], 2);
+
+        let mut arr = jobs();
+        build_min_heap_by_key(&mut arr, |job| job.priority);
+        assert_eq!(arr[0].name, "lint");
+
+        build_max_heap_by_key(&mut arr, |job| job.priority);
+        assert_eq!(arr[0].name, "deploy");
+    }
+
+    #[test]
+    fn heap_select_k_works() {
+        let mut arr = [5, 2, 9, 1, 7, 3, 8, 6, 4, 0];
+
+        let top = heap_select_k(&mut arr, 3);
+        top.sort();
+        assert_eq!(top, [7, 8, 9]);
+
+        assert!(heap_select_k(&mut arr, 0).is_empty());
+        assert_eq!(heap_select_k(&mut arr, 20).len(), 10);
+
+        let mut arr = jobs();
+        let top = heap_select_k_by_key(&mut arr, 2, |job| job.priority);
+        let mut names: Vec<_> = top.iter().map(|job| job.name).collect();
+        names.sort();
+        assert_eq!(names, ["deploy", "test"]);
+
+        let mut arr = jobs();
+        let bottom = heap_select_k_by(&mut arr, 1, |a, b| b.priority.cmp(&a.priority));
+        assert_eq!(bottom[0].name, "lint");
+    }
 }
@@ -1,16 +1,56 @@
+use std::cmp::Ordering;
+
 pub fn insertion_sort<T: Ord>(slice: &mut [T]) {
-    for i in 2..slice.len() {
-        for j in 0..i {
-            if slice[i] < slice[j] {
-                slice.swap(i, j);
-            }
+    insertion_sort_by(slice, T::cmp);
+}
+
+// Moves each element left past every greater one before it. Equal elements
+// never pass each other, so the sort is stable.
+pub fn insertion_sort_by<T, F>(slice: &mut [T], mut compare: F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    insertion_sort_from(slice, 1, &mut compare);
+}
+
+// Inserts `slice[sorted..]` one by one into the already sorted
+// `slice[..sorted]`.
+pub(crate) fn insertion_sort_from<T, F>(slice: &mut [T], sorted: usize, compare: &mut F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    for i in sorted.max(1)..slice.len() {
+        let mut j = i;
+
+        while j > 0 && compare(&slice[j], &slice[j - 1]) == Ordering::Less {
+            slice.swap(j, j - 1);
+            j -= 1;
         }
     }
 }
 
+pub fn insertion_sort_by_key<T, K, F>(slice: &mut [T], mut f: F)
+where
+    K: Ord,
+    F: FnMut(&T) -> K,
+{
+    insertion_sort_by(slice, |a, b| f(a).cmp(&f(b)));
+}
+
+pub fn insertion_sort_by_cached_key<T, K, F>(slice: &mut [T], f: F)
+where
+    K: Ord,
+    F: FnMut(&T) -> K,
+{
+    super::sort_by_cached_key(slice, f, |keys| {
+        insertion_sort_by(keys, |a, b| a.0.cmp(&b.0))
+    });
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::sort::tests::records;
 
     #[test]
     fn insertion_works() {
@@ -27,4 +67,27 @@ mod tests {
         insertion_sort(&mut arr);
         assert_eq!(arr, [1, 2, 3, 4, 5, 6]);
     }
+
+    #[test]
+    fn insertion_sort_by_works() {
+        let mut arr = [2, 1];
+        insertion_sort(&mut arr);
+        assert_eq!(arr, [1, 2]);
+
+        let mut expected = records();
+        expected.sort_by_key(|r| r.1);
+
+        let mut arr = records();
+        insertion_sort_by_key(&mut arr, |r| r.1);
+        assert_eq!(arr, expected);
+
+        let mut arr = records();
+        insertion_sort_by_cached_key(&mut arr, |r| r.1);
+        assert_eq!(arr, expected);
+
+        expected.sort_by_key(|r| std::cmp::Reverse(r.1));
+        let mut arr = records();
+        insertion_sort_by(&mut arr, |a, b| b.1.cmp(&a.1));
+        assert_eq!(arr, expected);
+    }
 }
@@ -0,0 +1,210 @@
+use std::cmp::Ordering;
+
+use super::heap::heap_sort_by;
+use super::insertion::insertion_sort_by;
+use super::quick::partition_three_way_by;
+
+// Slices this short are finished with insertion sort.
+const INSERTION_LEN: usize = 16;
+
+// Above this length the pivot is Tukey's ninther rather than a median of
+// three.
+const NINTHER_LEN: usize = 128;
+
+pub fn introsort<T: Ord>(arr: &mut [T]) {
+    introsort_by(arr, T::cmp);
+}
+
+// Quicksort with a three-way partition, which keeps runs of equal elements
+// out of the recursion, and a depth limit of 2 log2 n past which the slice
+// is heap sorted, so the worst case is O(n log n). Recursing only into the
+// smaller side bounds the stack at O(log n). Not stable.
+pub fn introsort_by<T, F>(arr: &mut [T], mut compare: F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    let limit = 2 * arr.len().max(1).ilog2() as usize;
+    sort(arr, limit, &mut compare);
+}
+
+pub fn introsort_by_key<T, K, F>(arr: &mut [T], mut f: F)
+where
+    K: Ord,
+    F: FnMut(&T) -> K,
+{
+    introsort_by(arr, |a, b| f(a).cmp(&f(b)));
+}
+
+fn sort<T, F>(mut arr: &mut [T], mut limit: usize, compare: &mut F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    while arr.len() > INSERTION_LEN {
+        if limit == 0 {
+            heap_sort_by(arr, &mut *compare);
+            return;
+        }
+
+        limit -= 1;
+
+        let pivot = choose_pivot(arr, compare);
+        let equal = partition_three_way_by(arr, pivot, &mut *compare);
+
+        let (left, rest) = arr.split_at_mut(equal.start);
+        let right = &mut rest[equal.len()..];
+
+        if left.len() < right.len() {
+            sort(left, limit, compare);
+            arr = right;
+        } else {
+            sort(right, limit, compare);
+            arr = left;
+        }
+    }
+
+    insertion_sort_by(arr, &mut *compare);
+}
+
+fn choose_pivot<T, F>(arr: &[T], compare: &mut F) -> usize
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    let len = arr.len();
+    let (a, b, c) = (0, len / 2, len - 1);
+
+    if len < NINTHER_LEN {
+        return median_of_three(arr, a, b, c, compare);
+    }
+
+    let step = len / 8;
+    let a = median_of_three(arr, a, a + step, a + 2 * step, compare);
+    let b = median_of_three(arr, b - step, b, b + step, compare);
+    let c = median_of_three(arr, c - 2 * step, c - step, c, compare);
+    median_of_three(arr, a, b, c, compare)
+}
+
+fn median_of_three<T, F>(arr: &[T], a: usize, b: usize, c: usize, compare: &mut F) -> usize
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    let less = |compare: &mut F, x: usize, y: usize| compare(&arr[x], &arr[y]) == Ordering::Less;
+
+    if less(compare, a, b) {
+        if less(compare, b, c) {
+            b
+        } else if less(compare, a, c) {
+            c
+        } else {
+            a
+        }
+    } else if less(compare, a, c) {
+        a
+    } else if less(compare, b, c) {
+        c
+    } else {
+        b
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::XorShift;
+    use crate::sort::tests::records;
+
+    fn counted(arr: &mut [u64]) -> usize {
+        let mut comparisons = 0;
+
+        introsort_by(arr, |a, b| {
+            comparisons += 1;
+            a.cmp(b)
+        });
+
+        comparisons
+    }
+
+    fn is_sorted(arr: &[u64]) -> bool {
+        arr.windows(2).all(|w| w[0] <= w[1])
+    }
+
+    #[test]
+    fn introsort_works() {
+        let mut arr0: [u8; 0] = [];
+        let mut arr1 = [1];
+        let mut arr = [5, 2, 4, 6, 1, 3];
+
+        introsort(&mut arr0);
+        assert_eq!(arr0, []);
+
+        introsort(&mut arr1);
+        assert_eq!(arr1, [1]);
+
+        introsort(&mut arr);
+        assert_eq!(arr, [1, 2, 3, 4, 5, 6]);
+
+        let mut expected = records();
+        expected.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+
+        let mut arr = records();
+        introsort_by_key(&mut arr, |r| (u32::MAX - r.1, r.0));
+        assert_eq!(arr, expected);
+    }
+
+    #[test]
+    fn random_input() {
+        let mut rng = XorShift::new(23);
+
+        for n in [17, 100, 129, 1000, 20_000] {
+            for keys in [3, 1 << 30] {
+                let mut arr: Vec<u64> = (0..n).map(|_| rng.next_below(keys)).collect();
+                let mut expected = arr.clone();
+                expected.sort();
+
+                introsort(&mut arr);
+                assert_eq!(arr, expected);
+            }
+        }
+    }
+
+    // Inputs that make the last-element Lomuto quicksort quadratic.
+    #[test]
+    fn degenerate_inputs_stay_n_log_n() {
+        let n = 20_000u64;
+        let bound = 4 * n as usize * n.ilog2() as usize;
+
+        let mut sorted: Vec<u64> = (0..n).collect();
+        assert!(counted(&mut sorted) < bound);
+
+        let mut reversed: Vec<u64> = (0..n).rev().collect();
+        assert!(counted(&mut reversed) < bound);
+        assert!(is_sorted(&reversed));
+
+        // Equal elements all land in the middle band and are never revisited.
+        let mut equal = vec![7; n as usize];
+        assert!(counted(&mut equal) < 2 * n as usize);
+    }
+
+    #[test]
+    fn heap_sort_fallback() {
+        let mut rng = XorShift::new(24);
+        let mut arr: Vec<u64> = (0..1000).map(|_| rng.next_below(1000)).collect();
+
+        sort(&mut arr, 0, &mut u64::cmp);
+        assert!(is_sorted(&arr));
+    }
+
+    #[test]
+    fn median_of_three_picks_middle() {
+        for (arr, expected) in [
+            ([1, 2, 3], 1),
+            ([1, 3, 2], 2),
+            ([2, 1, 3], 0),
+            ([2, 3, 1], 0),
+            ([3, 1, 2], 2),
+            ([3, 2, 1], 1),
+            ([2, 2, 2], 1),
+        ] {
+            assert_eq!(median_of_three(&arr, 0, 1, 2, &mut i32::cmp), expected);
+        }
+    }
+}
@@ -1,51 +1,333 @@
-pub fn merge_sort<T: Ord + Clone>(slice: &mut [T]) {
-    merge_ind(slice, 0, slice.len());
+use std::cmp::Ordering;
+use std::{mem, ptr, slice};
+
+use super::insertion::insertion_sort_by;
+
+// Runs this short are sorted in place by insertion sort instead of being
+// split further.
+const INSERTION_LEN: usize = 20;
+
+pub fn merge_sort<T: Ord>(slice: &mut [T]) {
+    merge_sort_by(slice, T::cmp);
 }
 
-pub fn merge_ind<T: Ord + Clone>(slice: &mut [T], low: usize, high: usize) {
-    if low + 1 < high {
-        let mid = (high - low) / 2 + low;
-        merge_ind(slice, low, mid);
-        merge_ind(slice, mid, high);
-        merge(slice, low, mid, high);
+// A stable merge sort. Needs no `Clone`: elements are moved through a single
+// scratch buffer of `slice.len() / 2` slots allocated up front.
+pub fn merge_sort_by<T, F>(slice: &mut [T], mut compare: F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    if mem::size_of::<T>() == 0 {
+        return;
+    }
+
+    if slice.len() <= INSERTION_LEN {
+        insertion_sort_by(slice, compare);
+        return;
     }
+
+    let mut buf = Vec::with_capacity(slice.len() / 2);
+    sort(slice, &mut buf, &mut compare);
+}
+
+pub fn merge_sort_by_key<T, K, F>(slice: &mut [T], mut f: F)
+where
+    K: Ord,
+    F: FnMut(&T) -> K,
+{
+    merge_sort_by(slice, |a, b| f(a).cmp(&f(b)));
 }
 
-fn merge<T: Ord + Clone>(slice: &mut [T], low: usize, pivot: usize, high: usize) {
-    assert!(low < pivot, "p = {}, q = {}", low, pivot);
-    assert!(pivot < high, "q = {}, r = {}", pivot, high);
+pub fn merge_sort_by_cached_key<T, K, F>(slice: &mut [T], f: F)
+where
+    K: Ord,
+    F: FnMut(&T) -> K,
+{
+    super::sort_by_cached_key(slice, f, |keys| merge_sort_by(keys, |a, b| a.0.cmp(&b.0)));
+}
 
-    let mut left = slice[low..pivot].to_vec();
-    let mut right = slice[pivot..high].to_vec();
+// Sorts `slice[low..high]`.
+pub fn merge_ind<T: Ord>(slice: &mut [T], low: usize, high: usize) {
+    merge_sort(&mut slice[low..high]);
+}
 
-    let mut i = 0;
-    let mut j = 0;
+fn sort<T, F>(slice: &mut [T], buf: &mut Vec<T>, compare: &mut F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    let len = slice.len();
 
-    for k in low..high {
-        match (left.get(i), right.get(j)) {
-            (None, None) => break,
-            (Some(l), None) => {
-                slice[k] = l.clone();
-            }
-            (None, Some(r)) => {
-                slice[k] = r.clone();
+    if len <= INSERTION_LEN {
+        insertion_sort_by(slice, &mut *compare);
+        return;
+    }
+
+    let mid = len / 2;
+    sort(&mut slice[..mid], buf, compare);
+    sort(&mut slice[mid..], buf, compare);
+
+    // The halves are already in order, as they often are in nearly sorted
+    // input.
+    if compare(&slice[mid - 1], &slice[mid]) != Ordering::Greater {
+        return;
+    }
+
+    merge(slice, mid, buf, compare);
+}
+
+// Merges the sorted runs `slice[..mid]` and `slice[mid..]`, keeping equal
+// elements in their original order. The shorter run is moved out into `buf`,
+// which grows to fit it if needed but is otherwise only used for its spare
+// capacity.
+pub(crate) fn merge<T, F>(slice: &mut [T], mid: usize, buf: &mut Vec<T>, compare: &mut F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    // A threshold no streak can reach never gallops.
+    let mut never = usize::MAX;
+    merge_galloping(slice, mid, buf, compare, &mut never);
+}
+
+// Once one run has supplied `min_gallop` elements in a row, the merge
+// switches to galloping: it searches for how many more that run supplies and
+// moves them as a block. It keeps galloping while the blocks are at least
+// `MIN_GALLOP` long, and adapts `min_gallop` to how well that went.
+pub(crate) const MIN_GALLOP: usize = 7;
+
+// `merge`, galloping as described above. `min_gallop` carries over between
+// merges of the same sort.
+pub(crate) fn merge_galloping<T, F>(
+    slice: &mut [T],
+    mid: usize,
+    buf: &mut Vec<T>,
+    compare: &mut F,
+    min_gallop: &mut usize,
+) where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    let len = slice.len();
+
+    if mid == 0 || mid == len || mem::size_of::<T>() == 0 {
+        return;
+    }
+
+    let short = mid.min(len - mid);
+    buf.reserve(short);
+
+    // SAFETY: `buf` has room for `short` elements and is never read through
+    // its length, which stays zero. Each element is bitwise moved into the
+    // buffer once and back into `slice` once. If `compare` panics, `Hole`
+    // moves whatever is still buffered into the gap left in `slice`, so every
+    // element ends up in `slice` exactly once.
+    unsafe {
+        let v = slice.as_mut_ptr();
+        let b = buf.as_mut_ptr();
+
+        if mid <= len - mid {
+            // Merge front to back. The gap sits between what has been
+            // written and what remains of the right run.
+            ptr::copy_nonoverlapping(v, b, mid);
+
+            let mut hole = Hole {
+                start: b,
+                end: b.add(mid),
+                dest: v,
+            };
+
+            let mut right = v.add(mid);
+            let end = v.add(len);
+
+            'merge: while hole.start < hole.end && right < end {
+                let (mut left_wins, mut right_wins) = (0, 0);
+
+                while left_wins.max(right_wins) < *min_gallop {
+                    // Only a strictly smaller right element goes first.
+                    if compare(&*right, &*hole.start) == Ordering::Less {
+                        ptr::copy_nonoverlapping(right, hole.dest, 1);
+                        right = right.add(1);
+                        (left_wins, right_wins) = (0, right_wins + 1);
+                    } else {
+                        ptr::copy_nonoverlapping(hole.start, hole.dest, 1);
+                        hole.start = hole.start.add(1);
+                        (left_wins, right_wins) = (left_wins + 1, 0);
+                    }
+
+                    hole.dest = hole.dest.add(1);
+
+                    if hole.start == hole.end || right == end {
+                        break 'merge;
+                    }
+                }
+
+                *min_gallop += 1;
+
+                loop {
+                    *min_gallop -= (*min_gallop > 1) as usize;
+
+                    let lefts = run(hole.start, hole.end);
+                    let n = gallop(lefts.len(), |i| {
+                        compare(&*right, &lefts[i]) != Ordering::Less
+                    });
+                    ptr::copy_nonoverlapping(hole.start, hole.dest, n);
+                    hole.start = hole.start.add(n);
+                    hole.dest = hole.dest.add(n);
+
+                    if hole.start == hole.end {
+                        break 'merge;
+                    }
+
+                    let rights = run(right, end);
+                    let m = gallop(rights.len(), |i| {
+                        compare(&rights[i], &*hole.start) == Ordering::Less
+                    });
+                    ptr::copy(right, hole.dest, m);
+                    right = right.add(m);
+                    hole.dest = hole.dest.add(m);
+
+                    if right == end {
+                        break 'merge;
+                    }
+
+                    if n < MIN_GALLOP && m < MIN_GALLOP {
+                        break;
+                    }
+                }
+
+                *min_gallop += 1;
             }
-            (Some(l), Some(r)) => {
-                if l <= r {
-                    slice[k] = l.clone();
-                    i += 1;
-                } else {
-                    slice[k] = r.clone();
-                    j += 1;
+        } else {
+            // Merge back to front. The gap sits between what remains of the
+            // left run and what has been written.
+            ptr::copy_nonoverlapping(v.add(mid), b, len - mid);
+
+            let mut hole = Hole {
+                start: b,
+                end: b.add(len - mid),
+                dest: v.add(mid),
+            };
+
+            let mut out = v.add(len);
+
+            'merge: while v < hole.dest && hole.start < hole.end {
+                let (mut left_wins, mut right_wins) = (0, 0);
+
+                while left_wins.max(right_wins) < *min_gallop {
+                    // Only a strictly greater left element goes last.
+                    if compare(&*hole.end.sub(1), &*hole.dest.sub(1)) == Ordering::Less {
+                        hole.dest = hole.dest.sub(1);
+                        out = out.sub(1);
+                        ptr::copy_nonoverlapping(hole.dest, out, 1);
+                        (left_wins, right_wins) = (left_wins + 1, 0);
+                    } else {
+                        hole.end = hole.end.sub(1);
+                        out = out.sub(1);
+                        ptr::copy_nonoverlapping(hole.end, out, 1);
+                        (left_wins, right_wins) = (0, right_wins + 1);
+                    }
+
+                    if v == hole.dest || hole.start == hole.end {
+                        break 'merge;
+                    }
                 }
+
+                *min_gallop += 1;
+
+                loop {
+                    *min_gallop -= (*min_gallop > 1) as usize;
+
+                    let lefts = run(v, hole.dest);
+                    let last = &*hole.end.sub(1);
+                    let n = gallop(lefts.len(), |i| {
+                        compare(last, &lefts[lefts.len() - 1 - i]) == Ordering::Less
+                    });
+                    hole.dest = hole.dest.sub(n);
+                    out = out.sub(n);
+                    ptr::copy(hole.dest, out, n);
+
+                    if v == hole.dest {
+                        break 'merge;
+                    }
+
+                    let rights = run(hole.start, hole.end);
+                    let last = &*hole.dest.sub(1);
+                    let m = gallop(rights.len(), |i| {
+                        compare(&rights[rights.len() - 1 - i], last) != Ordering::Less
+                    });
+                    hole.end = hole.end.sub(m);
+                    out = out.sub(m);
+                    ptr::copy_nonoverlapping(hole.end, out, m);
+
+                    if hole.start == hole.end {
+                        break 'merge;
+                    }
+
+                    if n < MIN_GALLOP && m < MIN_GALLOP {
+                        break;
+                    }
+                }
+
+                *min_gallop += 1;
             }
         }
     }
 }
 
+// The initialized elements `start..end` as a slice.
+unsafe fn run<'a, T>(start: *mut T, end: *mut T) -> &'a [T] {
+    slice::from_raw_parts(start, end.offset_from(start) as usize)
+}
+
+// The length of the prefix of `0..len` on which `holds` is true, given that
+// it holds on some prefix and nowhere after. Probes 0, 2, 6, 14, ... before
+// binary searching, so an answer of `k` costs O(log k) calls.
+pub(crate) fn gallop(len: usize, mut holds: impl FnMut(usize) -> bool) -> usize {
+    let mut lo = 0;
+    let mut hi = 1;
+
+    while hi <= len && holds(hi - 1) {
+        lo = hi;
+        hi = 2 * hi + 1;
+    }
+
+    let mut hi = (hi - 1).min(len);
+
+    while lo < hi {
+        let mid = lo + (hi - lo) / 2;
+
+        if holds(mid) {
+            lo = mid + 1;
+        } else {
+            hi = mid;
+        }
+    }
+
+    lo
+}
+
+// Buffered elements `start..end` that belong at `dest` in the slice being
+// merged. Dropping it moves them there.
+struct Hole<T> {
+    start: *mut T,
+    end: *mut T,
+    dest: *mut T,
+}
+
+impl<T> Drop for Hole<T> {
+    fn drop(&mut self) {
+        // SAFETY: `start..end` lies in the scratch buffer and the gap at
+        // `dest` in the slice is exactly that long.
+        unsafe {
+            let len = self.end.offset_from(self.start) as usize;
+            ptr::copy_nonoverlapping(self.start, self.dest, len);
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::rng::XorShift;
+    use crate::sort::tests::records;
 
     #[test]
     fn merge_works() {
@@ -62,4 +344,161 @@ mod tests {
         merge_sort(&mut arr);
         assert_eq!(arr, [1, 2, 3, 4, 5, 6]);
     }
+
+    #[test]
+    fn merge_sort_by_works() {
+        let mut arr = [1, 3, 2];
+        merge_sort(&mut arr);
+        assert_eq!(arr, [1, 2, 3]);
+
+        let mut expected = records();
+        expected.sort_by_key(|r| r.1);
+
+        let mut arr = records();
+        merge_sort_by_key(&mut arr, |r| r.1);
+        assert_eq!(arr, expected);
+
+        let mut arr = records();
+        merge_sort_by_cached_key(&mut arr, |r| r.1);
+        assert_eq!(arr, expected);
+
+        expected.sort_by_key(|r| std::cmp::Reverse(r.1));
+        let mut arr = records();
+        merge_sort_by(&mut arr, |a, b| b.1.cmp(&a.1));
+        assert_eq!(arr, expected);
+    }
+
+    // Deliberately not `Clone`.
+    #[derive(Debug, PartialEq)]
+    struct Handle {
+        key: u64,
+        id: usize,
+    }
+
+    fn handles(n: usize, keys: u64) -> Vec<Handle> {
+        let mut rng = XorShift::new(21);
+
+        (0..n)
+            .map(|id| Handle {
+                key: rng.next_below(keys),
+                id,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn stable_without_clone() {
+        for n in [0, 1, 2, 19, 20, 21, 100, 1000, 4097] {
+            let mut arr = handles(n, 50);
+            merge_sort_by_key(&mut arr, |h| h.key);
+
+            let mut expected = handles(n, 50);
+            expected.sort_by_key(|h| h.key);
+            assert_eq!(arr, expected);
+        }
+    }
+
+    #[test]
+    fn merge_both_directions() {
+        let mut buf = Vec::new();
+
+        // Runs of (key, position) with ties both within and across runs.
+        for mid in 0..=12 {
+            let mut arr: Vec<(usize, usize)> = (0..mid)
+                .map(|i| i / 2)
+                .chain((mid..12).map(|i| (i - mid) / 3))
+                .zip(0..)
+                .collect();
+
+            let mut expected = arr.clone();
+            expected.sort_by_key(|p| p.0);
+
+            merge(&mut arr, mid, &mut buf, &mut |a, b| a.0.cmp(&b.0));
+            assert_eq!(arr, expected);
+            assert!(buf.capacity() >= mid.min(12 - mid));
+        }
+    }
+
+    #[test]
+    fn galloping_merge_is_stable() {
+        let mut rng = XorShift::new(22);
+        let mut buf = Vec::new();
+
+        for _ in 0..200 {
+            // Runs drawn from a few keys, so there are long streaks and ties.
+            let len = 1 + rng.next_below(300) as usize;
+            let mid = rng.next_below(len as u64 + 1) as usize;
+            let keys = 1 + rng.next_below(20);
+
+            let mut left: Vec<u64> = (0..mid).map(|_| rng.next_below(keys)).collect();
+            let mut right: Vec<u64> = (mid..len).map(|_| rng.next_below(keys)).collect();
+            left.sort();
+            right.sort();
+
+            let mut arr: Vec<(u64, usize)> = left.into_iter().chain(right).zip(0..).collect();
+            let mut expected = arr.clone();
+            expected.sort_by_key(|p| p.0);
+
+            let mut min_gallop = 1 + rng.next_below(MIN_GALLOP as u64) as usize;
+            merge_galloping(
+                &mut arr,
+                mid,
+                &mut buf,
+                &mut |a, b| a.0.cmp(&b.0),
+                &mut min_gallop,
+            );
+            assert_eq!(arr, expected);
+            assert!(min_gallop >= 1);
+        }
+    }
+
+    #[test]
+    fn gallop_finds_prefix() {
+        for len in 0..40 {
+            for k in 0..=len {
+                let mut calls = 0;
+                let found = gallop(len, |i| {
+                    calls += 1;
+                    i < k
+                });
+
+                assert_eq!(found, k);
+                assert!(calls <= 2 * (usize::BITS - len.leading_zeros()) as usize + 1);
+            }
+        }
+    }
+
+    #[test]
+    fn sorted_input_skips_merges() {
+        let mut arr: Vec<u32> = (0..1000).collect();
+        let mut comparisons = 0;
+
+        merge_sort_by(&mut arr, |a, b| {
+            comparisons += 1;
+            a.cmp(b)
+        });
+
+        assert_eq!(comparisons, 999);
+        assert!(arr.iter().copied().eq(0..1000));
+    }
+
+    #[test]
+    fn panicking_comparator_keeps_elements() {
+        let mut arr: Vec<String> = (0..500).rev().map(|i| i.to_string()).collect();
+        let mut comparisons = 0;
+
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            merge_sort_by(&mut arr, |a, b| {
+                comparisons += 1;
+                assert!(comparisons < 2000, "comparator gave up");
+                a.cmp(b)
+            });
+        }));
+
+        assert!(result.is_err());
+
+        let mut seen: Vec<usize> = arr.iter().map(|s| s.parse().unwrap()).collect();
+        seen.sort();
+        assert!(seen.into_iter().eq(0..500));
+    }
 }
@@ -1,5 +1,74 @@
 pub mod bubble;
 pub mod heap;
 pub mod insertion;
+pub mod intro;
+pub mod merge;
+pub mod pdq;
 pub mod quick;
 pub mod selection;
+pub mod timsort;
+
+// Sorts `slice` by a key computed once per element. `sort` orders the
+// (key, index) pairs, after which the permutation they describe is applied
+// to `slice` in place.
+pub(crate) fn sort_by_cached_key<T, K, F, S>(slice: &mut [T], mut f: F, sort: S)
+where
+    K: Ord,
+    F: FnMut(&T) -> K,
+    S: FnOnce(&mut [(K, usize)]),
+{
+    let mut keys: Vec<(K, usize)> = slice.iter().map(&mut f).zip(0..).collect();
+    sort(&mut keys);
+
+    // `keys[i].1` is where the element for position `i` started out. Earlier
+    // swaps may have moved it on, so follow the chain until it lands at or
+    // after `i`.
+    for i in 0..slice.len() {
+        let mut index = keys[i].1;
+
+        while index < i {
+            index = keys[index].1;
+        }
+
+        keys[i].1 = index;
+        slice.swap(i, index);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    // Records with repeated scores, so that stable sorts can be told apart
+    // from unstable ones.
+    pub(super) fn records() -> Vec<(&'static str, u32)> {
+        vec![
+            ("ada", 3),
+            ("bob", 1),
+            ("cy", 3),
+            ("dee", 2),
+            ("eve", 1),
+            ("fay", 3),
+            ("gus", 2),
+            ("hal", 0),
+        ]
+    }
+
+    #[test]
+    fn sort_by_cached_key_applies_permutation() {
+        let mut arr = records();
+        let mut calls = 0;
+
+        super::sort_by_cached_key(
+            &mut arr,
+            |r| {
+                calls += 1;
+                r.1
+            },
+            |keys| keys.sort(),
+        );
+
+        let mut expected = records();
+        expected.sort_by_key(|r| r.1);
+        assert_eq!(arr, expected);
+        assert_eq!(calls, 8);
+    }
+}
@@ -0,0 +1,573 @@
+use std::cmp::{self, Ordering};
+use std::mem;
+
+use super::heap::heap_sort_by;
+use super::insertion::insertion_sort_from;
+use crate::rng::XorShift;
+
+// Slices this short are finished with insertion sort.
+const INSERTION_LEN: usize = 20;
+
+// Above this length the pivot is a median of three medians of three.
+const NINTHER_LEN: usize = 50;
+
+// The number of elements each side of the block partition classifies before
+// swapping. Offsets into a block must fit in a `u8`.
+const BLOCK: usize = 128;
+
+pub fn pdqsort<T: Ord>(arr: &mut [T]) {
+    pdqsort_by(arr, T::cmp);
+}
+
+// Pattern-defeating quicksort (Peters, 2021). Introsort that also spots
+// slices which are already sorted or partitioned and finishes them in
+// linear time, handles runs of elements equal to an earlier pivot in one
+// pass, and shuffles a few elements after every unbalanced partition so
+// adversarial inputs cannot keep it on its worst case. Falls back to heap
+// sort after log2 n unbalanced partitions. Not stable.
+pub fn pdqsort_by<T, F>(arr: &mut [T], mut compare: F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    if mem::size_of::<T>() == 0 {
+        return;
+    }
+
+    let limit = usize::BITS - arr.len().leading_zeros();
+    // Seeding from the length keeps every run reproducible.
+    let mut rng = XorShift::new(arr.len() as u64);
+    recurse(arr, &mut compare, None, limit, &mut rng);
+}
+
+pub fn pdqsort_by_key<T, K, F>(arr: &mut [T], mut f: F)
+where
+    K: Ord,
+    F: FnMut(&T) -> K,
+{
+    pdqsort_by(arr, |a, b| f(a).cmp(&f(b)));
+}
+
+// `pred` is the pivot of an enclosing partition that precedes `arr`, so
+// every element of `arr` is at least `pred`.
+fn recurse<'a, T, F>(
+    mut arr: &'a mut [T],
+    compare: &mut F,
+    mut pred: Option<&'a T>,
+    mut limit: u32,
+    rng: &mut XorShift,
+) where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    let mut was_balanced = true;
+    let mut was_partitioned = true;
+
+    loop {
+        let len = arr.len();
+
+        if len <= INSERTION_LEN {
+            insertion_sort_from(arr, 1, compare);
+            return;
+        }
+
+        if limit == 0 {
+            heap_sort_by(arr, &mut *compare);
+            return;
+        }
+
+        if !was_balanced {
+            break_patterns(arr, rng);
+            limit -= 1;
+        }
+
+        let (pivot, likely_sorted) = choose_pivot(arr, compare);
+
+        // The last partition was balanced and moved nothing, and the pivot
+        // samples were in order: try finishing with a few insertions.
+        if was_balanced && was_partitioned && likely_sorted && partial_insertion_sort(arr, compare)
+        {
+            return;
+        }
+
+        // A pivot equal to `pred` is the smallest element, so everything
+        // equal to it can be split off and never looked at again.
+        if let Some(pred) = pred {
+            if compare(pred, &arr[pivot]) != Ordering::Less {
+                let mid = partition_equal(arr, pivot, compare);
+                arr = &mut arr[mid..];
+                continue;
+            }
+        }
+
+        let (mid, moved_nothing) = partition(arr, pivot, compare);
+        was_balanced = cmp::min(mid, len - mid) >= len / 8;
+        was_partitioned = moved_nothing;
+
+        let (left, right) = arr.split_at_mut(mid);
+        let (pivot, right) = right.split_at_mut(1);
+        let pivot = &pivot[0];
+
+        if left.len() < right.len() {
+            recurse(left, compare, pred, limit, rng);
+            arr = right;
+            pred = Some(pivot);
+        } else {
+            recurse(right, compare, Some(pivot), limit, rng);
+            arr = left;
+        }
+    }
+}
+
+// Moves the pivot `arr[pivot]` to its final position with smaller elements
+// before it and the rest after, returning that position and whether the
+// slice was already partitioned.
+fn partition<T, F>(arr: &mut [T], pivot: usize, compare: &mut F) -> (usize, bool)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    arr.swap(0, pivot);
+    let (head, rest) = arr.split_at_mut(1);
+    let pivot = &head[0];
+
+    // Skip the prefix and suffix that are already on the right side.
+    let mut l = 0;
+    let mut r = rest.len();
+
+    while l < r && compare(&rest[l], pivot) == Ordering::Less {
+        l += 1;
+    }
+
+    while l < r && compare(&rest[r - 1], pivot) != Ordering::Less {
+        r -= 1;
+    }
+
+    let mid = l + partition_in_blocks(&mut rest[l..r], pivot, compare);
+    arr.swap(0, mid);
+    (mid, l >= r)
+}
+
+// Partitions `arr` into elements less than `pivot` and the rest, returning
+// the number of smaller ones. Each side first records which elements of a
+// block are misplaced and then swaps them pairwise; the comparisons feed
+// counters rather than branches, so random input costs no mispredictions.
+fn partition_in_blocks<T, F>(arr: &mut [T], pivot: &T, compare: &mut F) -> usize
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    // arr[..l] is less than the pivot and arr[r..] is not.
+    let mut l = 0;
+    let mut r = arr.len();
+
+    let mut block_l = BLOCK;
+    let mut offsets_l = [0u8; BLOCK];
+    let (mut start_l, mut end_l) = (0, 0);
+
+    let mut block_r = BLOCK;
+    let mut offsets_r = [0u8; BLOCK];
+    let (mut start_r, mut end_r) = (0, 0);
+
+    loop {
+        let is_done = r - l <= 2 * BLOCK;
+
+        // Shrink the blocks so that together they cover exactly what is
+        // left, keeping any block that still has misplaced elements whole.
+        if is_done {
+            let mut rem = r - l;
+
+            if start_l < end_l || start_r < end_r {
+                rem -= BLOCK;
+            }
+
+            if start_l < end_l {
+                block_r = rem;
+            } else if start_r < end_r {
+                block_l = rem;
+            } else {
+                block_l = rem / 2;
+                block_r = rem - block_l;
+            }
+        }
+
+        if start_l == end_l {
+            start_l = 0;
+            end_l = 0;
+
+            for i in 0..block_l {
+                offsets_l[end_l] = i as u8;
+                end_l += (compare(&arr[l + i], pivot) != Ordering::Less) as usize;
+            }
+        }
+
+        if start_r == end_r {
+            start_r = 0;
+            end_r = 0;
+
+            for i in 0..block_r {
+                offsets_r[end_r] = i as u8;
+                end_r += (compare(&arr[r - 1 - i], pivot) == Ordering::Less) as usize;
+            }
+        }
+
+        let count = cmp::min(end_l - start_l, end_r - start_r);
+
+        for k in 0..count {
+            let i = l + offsets_l[start_l + k] as usize;
+            let j = r - 1 - offsets_r[start_r + k] as usize;
+            arr.swap(i, j);
+        }
+
+        start_l += count;
+        start_r += count;
+
+        if start_l == end_l {
+            l += block_l;
+        }
+
+        if start_r == end_r {
+            r -= block_r;
+        }
+
+        if is_done {
+            break;
+        }
+    }
+
+    // At most one block still has misplaced elements, and it is all that
+    // lies between `l` and `r`. Move them to its far end, last first.
+    if start_l < end_l {
+        while start_l < end_l {
+            end_l -= 1;
+            arr.swap(l + offsets_l[end_l] as usize, r - 1);
+            r -= 1;
+        }
+
+        r
+    } else {
+        while start_r < end_r {
+            end_r -= 1;
+            arr.swap(l, r - 1 - offsets_r[end_r] as usize);
+            l += 1;
+        }
+
+        l
+    }
+}
+
+// Puts every element equal to the pivot `arr[pivot]` first, given that none
+// is smaller, and returns how many there are.
+fn partition_equal<T, F>(arr: &mut [T], pivot: usize, compare: &mut F) -> usize
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    arr.swap(0, pivot);
+    let (head, rest) = arr.split_at_mut(1);
+    let pivot = &head[0];
+
+    let mut l = 0;
+    let mut r = rest.len();
+
+    loop {
+        while l < r && compare(pivot, &rest[l]) != Ordering::Less {
+            l += 1;
+        }
+
+        while l < r && compare(pivot, &rest[r - 1]) == Ordering::Less {
+            r -= 1;
+        }
+
+        if l >= r {
+            break;
+        }
+
+        r -= 1;
+        rest.swap(l, r);
+        l += 1;
+    }
+
+    l + 1
+}
+
+// Sorts `arr` if it is only a handful of misplaced elements away from
+// sorted, giving up as soon as it looks like more work than that.
+fn partial_insertion_sort<T, F>(arr: &mut [T], compare: &mut F) -> bool
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    const MAX_STEPS: usize = 5;
+    const SHORTEST_SHIFTING: usize = 50;
+
+    let len = arr.len();
+    let mut i = 1;
+
+    for _ in 0..MAX_STEPS {
+        while i < len && compare(&arr[i], &arr[i - 1]) != Ordering::Less {
+            i += 1;
+        }
+
+        if i == len {
+            return true;
+        }
+
+        // Not worth shifting elements around in a slice this short.
+        if len < SHORTEST_SHIFTING {
+            return false;
+        }
+
+        arr.swap(i - 1, i);
+        insertion_sort_from(&mut arr[..i], i - 1, compare);
+
+        let mut j = i;
+
+        while j + 1 < len && compare(&arr[j + 1], &arr[j]) == Ordering::Less {
+            arr.swap(j, j + 1);
+            j += 1;
+        }
+    }
+
+    false
+}
+
+// Swaps three elements around the middle with random ones, scattering
+// whatever structure made the last partition unbalanced.
+fn break_patterns<T>(arr: &mut [T], rng: &mut XorShift) {
+    let len = arr.len();
+    let mid = len / 2;
+
+    for i in mid - 1..=mid + 1 {
+        arr.swap(i, rng.next_below(len as u64) as usize);
+    }
+}
+
+// Returns the index of a pivot and whether the sampled elements were
+// already in order. Samples that were strictly descending reverse the
+// slice, on the guess that all of it is.
+fn choose_pivot<T, F>(arr: &mut [T], compare: &mut F) -> (usize, bool)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    const MAX_SWAPS: usize = 4 * 3;
+
+    let len = arr.len();
+    let mut a = len / 4;
+    let mut b = len / 4 * 2;
+    let mut c = len / 4 * 3;
+    let mut swaps = 0;
+
+    if len >= 8 {
+        if len >= NINTHER_LEN {
+            for i in [&mut a, &mut b, &mut c] {
+                let (mut lo, mut hi) = (*i - 1, *i + 1);
+                sort3(arr, &mut lo, i, &mut hi, &mut swaps, compare);
+            }
+        }
+
+        sort3(arr, &mut a, &mut b, &mut c, &mut swaps, compare);
+    }
+
+    if swaps < MAX_SWAPS {
+        (b, swaps == 0)
+    } else {
+        arr.reverse();
+        (len - 1 - b, true)
+    }
+}
+
+// Orders the indices `a`, `b` and `c` by the elements they point at.
+fn sort3<T, F>(
+    arr: &[T],
+    a: &mut usize,
+    b: &mut usize,
+    c: &mut usize,
+    swaps: &mut usize,
+    compare: &mut F,
+) where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    let mut sort2 = |a: &mut usize, b: &mut usize| {
+        if compare(&arr[*b], &arr[*a]) == Ordering::Less {
+            mem::swap(a, b);
+            *swaps += 1;
+        }
+    };
+
+    sort2(a, b);
+    sort2(b, c);
+    sort2(a, b);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sort::tests::records;
+
+    fn counted(arr: &mut [u64]) -> usize {
+        let mut comparisons = 0;
+
+        pdqsort_by(arr, |a, b| {
+            comparisons += 1;
+            a.cmp(b)
+        });
+
+        comparisons
+    }
+
+    fn n_log_n(n: usize) -> usize {
+        n * n.ilog2() as usize
+    }
+
+    #[test]
+    fn pdqsort_works() {
+        let mut arr0: [u8; 0] = [];
+        let mut arr1 = [1];
+        let mut arr = [5, 2, 4, 6, 1, 3];
+
+        pdqsort(&mut arr0);
+        assert_eq!(arr0, []);
+
+        pdqsort(&mut arr1);
+        assert_eq!(arr1, [1]);
+
+        pdqsort(&mut arr);
+        assert_eq!(arr, [1, 2, 3, 4, 5, 6]);
+
+        let mut expected = records();
+        expected.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+
+        let mut arr = records();
+        pdqsort_by_key(&mut arr, |r| (u32::MAX - r.1, r.0));
+        assert_eq!(arr, expected);
+    }
+
+    #[test]
+    fn matches_std_on_random_input() {
+        let mut rng = XorShift::new(24);
+
+        for n in [21, 50, 100, 257, 1000, 20_000] {
+            for keys in [2, 100, 1 << 40] {
+                let mut arr: Vec<u64> = (0..n).map(|_| rng.next_below(keys)).collect();
+                let mut expected = arr.clone();
+                expected.sort_unstable();
+
+                pdqsort(&mut arr);
+                assert_eq!(arr, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn adversarial_inputs() {
+        let n = 10_000u64;
+        let k = n / 2;
+
+        let organ_pipe: Vec<u64> = (0..k).chain((0..k).rev()).collect();
+        let sawtooth: Vec<u64> = (0..n).map(|i| i % 64).collect();
+        let reverse_sawtooth: Vec<u64> = (0..n).map(|i| 63 - i % 64).collect();
+        let pushed_front: Vec<u64> = (1..n).chain([0]).collect();
+
+        // Musser's median-of-three killer: every median of the first, middle
+        // and last element is the second smallest.
+        let mut killer = vec![0; n as usize];
+
+        for i in 1..=k {
+            if i % 2 == 1 {
+                killer[i as usize - 1] = i;
+                killer[i as usize] = k + i;
+            }
+
+            killer[(k + i) as usize - 1] = 2 * i;
+        }
+
+        for mut arr in [organ_pipe, sawtooth, reverse_sawtooth, pushed_front, killer] {
+            let mut expected = arr.clone();
+            expected.sort_unstable();
+
+            let comparisons = counted(&mut arr);
+            assert_eq!(arr, expected);
+            assert!(
+                comparisons < 3 * n_log_n(n as usize),
+                "comparisons = {}",
+                comparisons
+            );
+        }
+    }
+
+    // McIlroy's adversary (1999) decides the order of elements only as the
+    // sort compares them, and always so the pivot candidate ends up small.
+    // It drives any plain quicksort quadratic; the heap sort fallback and
+    // pattern breaking keep pdqsort at n log n.
+    #[test]
+    fn mcilroy_adversary() {
+        let n = 5000;
+        let gas = n;
+        let mut value = vec![gas; n];
+        let mut solid = 0;
+        let mut candidate = 0;
+        let mut comparisons = 0;
+
+        let mut arr: Vec<usize> = (0..n).collect();
+
+        pdqsort_by(&mut arr, |&x, &y| {
+            comparisons += 1;
+
+            if value[x] == gas && value[y] == gas {
+                let frozen = if x == candidate { x } else { y };
+                value[frozen] = solid;
+                solid += 1;
+            }
+
+            if value[x] == gas {
+                candidate = x;
+            } else if value[y] == gas {
+                candidate = y;
+            }
+
+            value[x].cmp(&value[y])
+        });
+
+        assert!(arr.windows(2).all(|w| value[w[0]] <= value[w[1]]));
+        assert!(
+            comparisons < 4 * n_log_n(n),
+            "comparisons = {}",
+            comparisons
+        );
+    }
+
+    #[test]
+    fn linear_on_presorted_input() {
+        let n = 10_000;
+
+        let mut sorted: Vec<u64> = (0..n).collect();
+        assert!(counted(&mut sorted) < 2 * n as usize);
+
+        let mut reversed: Vec<u64> = (0..n).rev().collect();
+        assert!(counted(&mut reversed) < 2 * n as usize);
+        assert!(reversed.iter().copied().eq(0..n));
+
+        let mut equal = vec![7; n as usize];
+        assert!(counted(&mut equal) < 2 * n as usize);
+    }
+
+    #[test]
+    fn block_partition() {
+        let mut rng = XorShift::new(25);
+
+        for n in [0, 1, 127, 128, 129, 255, 256, 257, 1000] {
+            let mut arr: Vec<u64> = (0..n).map(|_| rng.next_below(100)).collect();
+            let smaller = arr.iter().filter(|&&x| x < 50).count();
+
+            let mid = partition_in_blocks(&mut arr, &50, &mut u64::cmp);
+            assert_eq!(mid, smaller);
+            assert!(arr[..mid].iter().all(|&x| x < 50));
+            assert!(arr[mid..].iter().all(|&x| x >= 50));
+        }
+    }
+
+    #[test]
+    fn partition_equal_splits_off_pivot() {
+        let mut arr = [3, 5, 3, 9, 3, 4, 3];
+        let mid = partition_equal(&mut arr, 0, &mut i32::cmp);
+
+        assert_eq!(mid, 4);
+        assert!(arr[..mid].iter().all(|&x| x == 3));
+        assert!(arr[mid..].iter().all(|&x| x > 3));
+    }
+}
@@ -1,56 +1,319 @@
-use std::fmt::Debug;
+use std::cmp::Ordering;
+use std::ops::Range;
 
-pub fn quicksort<T: Ord + Debug>(arr: &mut [T]) {
-    let len = arr.len();
-    quicksort_rec(arr, 0, len);
+pub fn quicksort<T: Ord>(arr: &mut [T]) {
+    quicksort_by(arr, T::cmp);
+}
+
+pub fn quicksort_by<T, F>(arr: &mut [T], mut compare: F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    quicksort_rec(arr, &mut compare);
+}
+
+pub fn quicksort_by_key<T, K, F>(arr: &mut [T], mut f: F)
+where
+    K: Ord,
+    F: FnMut(&T) -> K,
+{
+    quicksort_by(arr, |a, b| f(a).cmp(&f(b)));
+}
+
+fn quicksort_rec<T, F>(arr: &mut [T], compare: &mut F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    if arr.len() < 2 {
+        return;
+    }
+
+    let last = arr.len() - 1;
+    let pivot = partition_lomuto_by(arr, last, &mut *compare);
+    let (left, right) = arr.split_at_mut(pivot);
+
+    quicksort_rec(left, compare);
+    quicksort_rec(&mut right[1..], compare);
+}
+
+pub fn dual_pivot_quicksort<T: Ord>(arr: &mut [T]) {
+    dual_pivot_quicksort_by(arr, T::cmp);
 }
 
-fn quicksort_rec<T: Ord + Debug>(arr: &mut [T], low: usize, high: usize) {
-    if low + 1 >= high {
+// Yaroslavskiy's quicksort: two pivots split each slice three ways, which
+// takes fewer passes over memory than one pivot. The pivots are taken from
+// a third and two thirds of the way in, so sorted input is not quadratic.
+pub fn dual_pivot_quicksort_by<T, F>(arr: &mut [T], mut compare: F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    dual_pivot_rec(arr, &mut compare);
+}
+
+pub fn dual_pivot_quicksort_by_key<T, K, F>(arr: &mut [T], mut f: F)
+where
+    K: Ord,
+    F: FnMut(&T) -> K,
+{
+    dual_pivot_quicksort_by(arr, |a, b| f(a).cmp(&f(b)));
+}
+
+fn dual_pivot_rec<T, F>(arr: &mut [T], compare: &mut F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    let len = arr.len();
+
+    if len < 2 {
         return;
     }
 
-    let pivot = partition(arr, low, high - 1);
+    let (p, q) = partition_dual_pivot_by(arr, len / 3, 2 * len / 3, &mut *compare);
+    let (left, rest) = arr.split_at_mut(p);
+    let (middle, right) = rest.split_at_mut(q - p);
+
+    dual_pivot_rec(left, compare);
+    dual_pivot_rec(&mut right[1..], compare);
 
-    quicksort_rec(arr, low, pivot);
-    quicksort_rec(arr, pivot + 1, high);
+    // With equal pivots everything between them equals both.
+    if compare(&middle[0], &right[0]) == Ordering::Less {
+        dual_pivot_rec(&mut middle[1..], compare);
+    }
+}
+
+// Partitions `arr` around the element at `pivot` and returns where that
+// element ends up: everything before it is smaller, everything after it is
+// not. The pivot is swapped to the end and the rest scanned once (Lomuto).
+pub fn partition_lomuto<T: Ord>(arr: &mut [T], pivot: usize) -> usize {
+    partition_lomuto_by(arr, pivot, T::cmp)
 }
 
-fn partition<T: Ord + Debug>(arr: &mut [T], low: usize, high: usize) -> usize {
-    let mut pivot = low;
+pub fn partition_lomuto_by<T, F>(arr: &mut [T], pivot: usize, mut compare: F) -> usize
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    let last = arr.len() - 1;
+    arr.swap(pivot, last);
 
-    for i in low..high {
-        if arr[i] < arr[high] {
-            arr.swap(pivot, i);
-            pivot += 1;
+    let mut mid = 0;
+
+    for i in 0..last {
+        if compare(&arr[i], &arr[last]) == Ordering::Less {
+            arr.swap(mid, i);
+            mid += 1;
         }
     }
 
-    arr.swap(pivot, high);
+    arr.swap(mid, last);
+
+    mid
+}
+
+// Like `partition_lomuto`, but the two ends scan towards each other and
+// swap pairs of misplaced elements (Hoare). Elements equal to the pivot
+// stop both scans, so they end up spread over both sides rather than piled
+// on one, and there are about a third as many swaps.
+pub fn partition_hoare<T: Ord>(arr: &mut [T], pivot: usize) -> usize {
+    partition_hoare_by(arr, pivot, T::cmp)
+}
+
+pub fn partition_hoare_by<T, F>(arr: &mut [T], pivot: usize, mut compare: F) -> usize
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    arr.swap(0, pivot);
+    let (head, rest) = arr.split_at_mut(1);
+    let pivot = &head[0];
+
+    // rest[..l] is at most the pivot and rest[r..] at least.
+    let mut l = 0;
+    let mut r = rest.len();
+
+    loop {
+        while l < r && compare(&rest[l], pivot) == Ordering::Less {
+            l += 1;
+        }
+
+        while l < r && compare(&rest[r - 1], pivot) == Ordering::Greater {
+            r -= 1;
+        }
+
+        if l >= r {
+            break;
+        }
+
+        rest.swap(l, r - 1);
+        l += 1;
+        r -= 1;
+    }
+
+    arr.swap(0, l);
+
+    l
+}
+
+// Partitions `arr` into elements less than, equal to and greater than the
+// element at `pivot` (Dijkstra's Dutch national flag), returning the range
+// of equal ones.
+pub fn partition_three_way<T: Ord>(arr: &mut [T], pivot: usize) -> Range<usize> {
+    partition_three_way_by(arr, pivot, T::cmp)
+}
+
+pub fn partition_three_way_by<T, F>(arr: &mut [T], pivot: usize, mut compare: F) -> Range<usize>
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    arr.swap(0, pivot);
+
+    let mut lt = 1;
+    let mut i = 1;
+    let mut gt = arr.len();
+
+    // arr[1..lt] < pivot, arr[lt..i] == pivot, arr[gt..] > pivot.
+    while i < gt {
+        match compare(&arr[i], &arr[0]) {
+            Ordering::Less => {
+                arr.swap(lt, i);
+                lt += 1;
+                i += 1;
+            }
+            Ordering::Greater => {
+                gt -= 1;
+                arr.swap(i, gt);
+            }
+            Ordering::Equal => i += 1,
+        }
+    }
+
+    arr.swap(0, lt - 1);
+
+    lt - 1..gt
+}
 
-    pivot
+// Partitions `arr` around the elements at `p` and `q` and returns where the
+// smaller and the larger of them end up, say at `i` and `j`. Then
+// `arr[..i]` is less than `arr[i]`, `arr[i + 1..j]` lies between the two,
+// inclusive, and `arr[j + 1..]` is greater than `arr[j]`.
+pub fn partition_dual_pivot<T: Ord>(arr: &mut [T], p: usize, q: usize) -> (usize, usize) {
+    partition_dual_pivot_by(arr, p, q, T::cmp)
 }
 
-// fn partition2<T: Ord + Debug>(arr: &mut [T], low: usize, high: usize) -> usize {
-//     0
-// }
+pub fn partition_dual_pivot_by<T, F>(
+    arr: &mut [T],
+    p: usize,
+    q: usize,
+    mut compare: F,
+) -> (usize, usize)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    assert!(p != q, "the two pivots must be different elements");
+
+    let last = arr.len() - 1;
+    arr.swap(0, p);
+    arr.swap(last, if q == 0 { p } else { q });
+
+    if compare(&arr[last], &arr[0]) == Ordering::Less {
+        arr.swap(0, last);
+    }
+
+    let mut lt = 1;
+    let mut i = 1;
+    let mut gt = last;
+
+    // arr[1..lt] < low, arr[lt..i] in [low, high], arr[gt..last] > high.
+    while i < gt {
+        if compare(&arr[i], &arr[0]) == Ordering::Less {
+            arr.swap(lt, i);
+            lt += 1;
+            i += 1;
+        } else if compare(&arr[i], &arr[last]) == Ordering::Greater {
+            gt -= 1;
+            arr.swap(i, gt);
+        } else {
+            i += 1;
+        }
+    }
+
+    arr.swap(0, lt - 1);
+    arr.swap(last, gt);
+
+    (lt - 1, gt)
+}
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::rng::XorShift;
+    use crate::sort::tests::records;
 
     #[test]
     fn partition_works() {
         let mut arr = [5, 2, 4, 6, 1, 3];
 
         let len = arr.len();
-        let pivot = partition(&mut arr, 0, len - 1);
+        let pivot = partition_lomuto(&mut arr, len - 1);
 
         assert_eq!(&arr[pivot], &3);
         assert_eq!(&arr[..pivot], [2, 1]);
         assert_eq!(&arr[pivot + 1..], [6, 5, 4]);
     }
 
+    #[test]
+    fn partition_schemes() {
+        let mut rng = XorShift::new(25);
+
+        for n in [1, 2, 3, 10, 100] {
+            for keys in [3, 1000] {
+                let data: Vec<u64> = (0..n).map(|_| rng.next_below(keys)).collect();
+                let i = rng.next_below(n) as usize;
+                let value = data[i];
+
+                for scheme in [partition_lomuto::<u64>, partition_hoare::<u64>] {
+                    let mut arr = data.clone();
+                    let mid = scheme(&mut arr, i);
+
+                    assert_eq!(arr[mid], value);
+                    assert!(arr[..mid].iter().all(|&x| x <= value));
+                    assert!(arr[mid + 1..].iter().all(|&x| x >= value));
+                }
+
+                let mut arr = data.clone();
+                let equal = partition_three_way(&mut arr, i);
+
+                assert!(arr[..equal.start].iter().all(|&x| x < value));
+                assert!(arr[equal.clone()].iter().all(|&x| x == value));
+                assert!(arr[equal.end..].iter().all(|&x| x > value));
+            }
+        }
+    }
+
+    #[test]
+    fn hoare_balances_equal_elements() {
+        let mut arr = [7; 100];
+        assert_eq!(partition_hoare(&mut arr, 0), 50);
+        assert_eq!(partition_lomuto(&mut arr, 0), 0);
+    }
+
+    #[test]
+    fn dual_pivot_buckets_records() {
+        // Split the records into scores below 80, 80 to 90 and above 90.
+        let mut arr = records();
+        arr.push(("low", 80));
+        arr.push(("high", 90));
+
+        let len = arr.len();
+        let (i, j) = partition_dual_pivot_by(&mut arr, len - 1, len - 2, |a, b| a.1.cmp(&b.1));
+
+        assert_eq!((arr[i], arr[j]), (("low", 80), ("high", 90)));
+        assert!(arr[..i].iter().all(|r| r.1 < 80));
+        assert!(arr[i + 1..j].iter().all(|r| (80..=90).contains(&r.1)));
+        assert!(arr[j + 1..].iter().all(|r| r.1 > 90));
+
+        let mut arr = [4, 4, 4, 4];
+        assert_eq!(partition_dual_pivot(&mut arr, 3, 0), (0, 3));
+    }
+
     #[test]
     fn quicksort_works() {
         let mut arr0: [u8; 0] = [];
@@ -66,4 +329,53 @@ mod tests {
         quicksort(&mut arr);
         assert_eq!(arr, [1, 2, 3, 4, 5, 6]);
     }
+
+    #[test]
+    fn quicksort_by_works() {
+        // Quicksort is unstable, so break ties on the name.
+        let mut expected = records();
+        expected.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+
+        let mut arr = records();
+        quicksort_by(&mut arr, |a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        assert_eq!(arr, expected);
+
+        let mut arr = records();
+        quicksort_by_key(&mut arr, |r| (u32::MAX - r.1, r.0));
+        assert_eq!(arr, expected);
+    }
+
+    #[test]
+    fn dual_pivot_quicksort_works() {
+        let mut arr0: [u8; 0] = [];
+        let mut arr = [5, 2, 4, 6, 1, 3];
+
+        dual_pivot_quicksort(&mut arr0);
+        assert_eq!(arr0, []);
+
+        dual_pivot_quicksort(&mut arr);
+        assert_eq!(arr, [1, 2, 3, 4, 5, 6]);
+
+        let mut rng = XorShift::new(26);
+
+        for keys in [2, 50, 1 << 30] {
+            let mut arr: Vec<u64> = (0..2000).map(|_| rng.next_below(keys)).collect();
+            let mut expected = arr.clone();
+            expected.sort();
+
+            dual_pivot_quicksort(&mut arr);
+            assert_eq!(arr, expected);
+        }
+
+        let mut sorted: Vec<u64> = (0..2000).collect();
+        dual_pivot_quicksort(&mut sorted);
+        assert!(sorted.iter().copied().eq(0..2000));
+
+        let mut expected = records();
+        expected.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+
+        let mut arr = records();
+        dual_pivot_quicksort_by_key(&mut arr, |r| (u32::MAX - r.1, r.0));
+        assert_eq!(arr, expected);
+    }
 }
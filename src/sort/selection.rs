@@ -1,16 +1,34 @@
+use std::cmp::Ordering;
+
 pub fn selection_sort<T: Ord>(slice: &mut [T]) {
+    selection_sort_by(slice, T::cmp);
+}
+
+pub fn selection_sort_by<T, F>(slice: &mut [T], mut compare: F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
     for i in 0..slice.len() {
         for j in i..slice.len() {
-            if slice[j] < slice[i] {
+            if compare(&slice[j], &slice[i]) == Ordering::Less {
                 slice.swap(i, j);
             }
         }
     }
 }
 
+pub fn selection_sort_by_key<T, K, F>(slice: &mut [T], mut f: F)
+where
+    K: Ord,
+    F: FnMut(&T) -> K,
+{
+    selection_sort_by(slice, |a, b| f(a).cmp(&f(b)));
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::sort::tests::records;
 
     #[test]
     fn selection_sort_works() {
@@ -27,4 +45,19 @@ mod tests {
         selection_sort(&mut arr);
         assert_eq!(arr, [1, 2, 3, 4, 5, 6]);
     }
+
+    #[test]
+    fn selection_sort_by_works() {
+        // Selection sort is unstable, so break ties on the name.
+        let mut expected = records();
+        expected.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+
+        let mut arr = records();
+        selection_sort_by(&mut arr, |a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        assert_eq!(arr, expected);
+
+        let mut arr = records();
+        selection_sort_by_key(&mut arr, |r| (u32::MAX - r.1, r.0));
+        assert_eq!(arr, expected);
+    }
 }
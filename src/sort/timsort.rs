@@ -0,0 +1,315 @@
+use std::cmp::Ordering;
+use std::mem;
+
+use super::insertion::insertion_sort_from;
+use super::merge::{gallop, merge_galloping, MIN_GALLOP};
+
+pub fn timsort<T: Ord>(slice: &mut [T]) {
+    timsort_by(slice, T::cmp);
+}
+
+// A stable, natural merge sort. It splits `slice` into the runs already
+// present, extends short ones to `min_run` with insertion sort, and merges
+// neighbouring runs as their lengths dictate. Sorted or reversed input costs
+// n - 1 comparisons and nothing else.
+pub fn timsort_by<T, F>(slice: &mut [T], mut compare: F)
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    let len = slice.len();
+
+    if len < 2 || mem::size_of::<T>() == 0 {
+        return;
+    }
+
+    let min_run = min_run(len);
+    let mut sort = Timsort {
+        runs: Vec::new(),
+        buf: Vec::new(),
+        min_gallop: MIN_GALLOP,
+    };
+
+    let mut start = 0;
+
+    while start < len {
+        let mut run = find_run(&mut slice[start..], &mut compare);
+
+        if run < min_run {
+            let end = len.min(start + min_run);
+            insertion_sort_from(&mut slice[start..end], run, &mut compare);
+            run = end - start;
+        }
+
+        sort.runs.push(Run { start, len: run });
+        start += run;
+        sort.collapse(slice, &mut compare, false);
+    }
+
+    sort.collapse(slice, &mut compare, true);
+    debug_assert_eq!(sort.runs.len(), 1);
+}
+
+pub fn timsort_by_key<T, K, F>(slice: &mut [T], mut f: F)
+where
+    K: Ord,
+    F: FnMut(&T) -> K,
+{
+    timsort_by(slice, |a, b| f(a).cmp(&f(b)));
+}
+
+pub fn timsort_by_cached_key<T, K, F>(slice: &mut [T], f: F)
+where
+    K: Ord,
+    F: FnMut(&T) -> K,
+{
+    super::sort_by_cached_key(slice, f, |keys| timsort_by(keys, |a, b| a.0.cmp(&b.0)));
+}
+
+// A length in 32..=64 such that `n / min_run` is a power of two or just
+// below one, so the final merges stay balanced.
+fn min_run(mut n: usize) -> usize {
+    let mut rest = 0;
+
+    while n >= 64 {
+        rest |= n & 1;
+        n >>= 1;
+    }
+
+    n + rest
+}
+
+// The length of the run at the start of `slice`. A strictly descending run
+// is reversed in place; strictness keeps equal elements in order.
+fn find_run<T, F>(slice: &mut [T], compare: &mut F) -> usize
+where
+    F: FnMut(&T, &T) -> Ordering,
+{
+    if slice.len() < 2 {
+        return slice.len();
+    }
+
+    let mut end = 2;
+
+    if compare(&slice[1], &slice[0]) == Ordering::Less {
+        while end < slice.len() && compare(&slice[end], &slice[end - 1]) == Ordering::Less {
+            end += 1;
+        }
+
+        slice[..end].reverse();
+    } else {
+        while end < slice.len() && compare(&slice[end], &slice[end - 1]) != Ordering::Less {
+            end += 1;
+        }
+    }
+
+    end
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Run {
+    start: usize,
+    len: usize,
+}
+
+struct Timsort<T> {
+    runs: Vec<Run>,
+    buf: Vec<T>,
+    min_gallop: usize,
+}
+
+impl<T> Timsort<T> {
+    // Merges runs until, for the lengths A, B, C, D of the top four,
+    // B > C + D, A > B + C and C > D. Checking A as well as B is the fix
+    // from de Gouw et al. (2015): without it the invariant can fail deeper
+    // in the stack. With `force`, merges everything into one run.
+    fn collapse<F>(&mut self, slice: &mut [T], compare: &mut F, force: bool)
+    where
+        F: FnMut(&T, &T) -> Ordering,
+    {
+        while self.runs.len() > 1 {
+            let n = self.runs.len() - 2;
+            let len = |i: usize| self.runs[i].len;
+
+            let i = if force
+                || (n > 0 && len(n - 1) <= len(n) + len(n + 1))
+                || (n > 1 && len(n - 2) <= len(n - 1) + len(n))
+            {
+                if n > 0 && len(n - 1) < len(n + 1) {
+                    n - 1
+                } else {
+                    n
+                }
+            } else if len(n) <= len(n + 1) {
+                n
+            } else {
+                break;
+            };
+
+            self.merge_at(i, slice, compare);
+        }
+    }
+
+    // Merges runs `i` and `i + 1`.
+    fn merge_at<F>(&mut self, i: usize, slice: &mut [T], compare: &mut F)
+    where
+        F: FnMut(&T, &T) -> Ordering,
+    {
+        let a = self.runs[i];
+        let b = self.runs.remove(i + 1);
+        self.runs[i].len += b.len;
+
+        let slice = &mut slice[a.start..b.start + b.len];
+        let mid = a.len;
+
+        // Elements of the first run no greater than the second run's first
+        // element are already in place, as are elements of the second run
+        // no less than the first run's last element.
+        let skip = gallop(mid, |i| compare(&slice[mid], &slice[i]) != Ordering::Less);
+
+        if skip == mid {
+            return;
+        }
+
+        let keep = gallop(slice.len() - mid, |i| {
+            compare(&slice[slice.len() - 1 - i], &slice[mid - 1]) != Ordering::Less
+        });
+
+        let end = slice.len() - keep;
+        merge_galloping(
+            &mut slice[skip..end],
+            mid - skip,
+            &mut self.buf,
+            compare,
+            &mut self.min_gallop,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::XorShift;
+    use crate::sort::tests::records;
+
+    fn counted(slice: &mut [u64]) -> usize {
+        let mut comparisons = 0;
+
+        timsort_by(slice, |a, b| {
+            comparisons += 1;
+            a.cmp(b)
+        });
+
+        comparisons
+    }
+
+    #[test]
+    fn timsort_works() {
+        let mut arr0: [u8; 0] = [];
+        let mut arr1 = [1];
+        let mut arr = [5, 2, 4, 6, 1, 3];
+
+        timsort(&mut arr0);
+        assert_eq!(arr0, []);
+
+        timsort(&mut arr1);
+        assert_eq!(arr1, [1]);
+
+        timsort(&mut arr);
+        assert_eq!(arr, [1, 2, 3, 4, 5, 6]);
+    }
+
+    #[test]
+    fn stable_on_random_input() {
+        let mut rng = XorShift::new(22);
+
+        for n in [10, 63, 64, 65, 200, 1000, 5000] {
+            for keys in [2, 30, 1 << 20] {
+                let data: Vec<(u64, usize)> = (0..n).map(|i| (rng.next_below(keys), i)).collect();
+
+                let mut arr = data.clone();
+                timsort_by_key(&mut arr, |p| p.0);
+
+                let mut expected = data;
+                expected.sort_by_key(|p| p.0);
+                assert_eq!(arr, expected);
+            }
+        }
+
+        let mut expected = records();
+        expected.sort_by_key(|r| r.1);
+
+        let mut arr = records();
+        timsort_by_cached_key(&mut arr, |r| r.1);
+        assert_eq!(arr, expected);
+    }
+
+    #[test]
+    fn linear_on_presorted_input() {
+        let n = 10_000;
+
+        let mut sorted: Vec<u64> = (0..n).collect();
+        assert_eq!(counted(&mut sorted), n as usize - 1);
+
+        let mut reversed: Vec<u64> = (0..n).rev().collect();
+        assert_eq!(counted(&mut reversed), n as usize - 1);
+        assert!(reversed.iter().copied().eq(0..n));
+
+        // Equal elements do not form a descending run, so nothing moves.
+        let mut equal = vec![(7, 0); 100];
+        equal.iter_mut().enumerate().for_each(|(i, p)| p.1 = i);
+        timsort_by_key(&mut equal, |p| p.0);
+        assert!(equal.iter().map(|p| p.1).eq(0..100));
+    }
+
+    #[test]
+    fn appended_tail() {
+        let mut rng = XorShift::new(23);
+        let n = 10_000;
+        let mut arr: Vec<u64> = (0..n).map(|i| 2 * i).collect();
+        arr.extend((0..100).map(|_| rng.next_below(2 * n)));
+
+        let mut expected = arr.clone();
+        expected.sort();
+
+        let comparisons = counted(&mut arr);
+        assert_eq!(arr, expected);
+        assert!(
+            comparisons < 2 * n as usize,
+            "comparisons = {}",
+            comparisons
+        );
+    }
+
+    #[test]
+    fn galloping_merges_blocks() {
+        // Interleaved blocks of 500: one long run per block, and every merge
+        // moves whole blocks at a time.
+        let mut arr: Vec<u64> = (0..20)
+            .flat_map(|block| {
+                let base = if block % 2 == 0 {
+                    block / 2
+                } else {
+                    10 + block / 2
+                };
+                (0..500).map(move |i| base * 500 + i)
+            })
+            .collect();
+
+        let comparisons = counted(&mut arr);
+        assert!(arr.iter().copied().eq(0..10_000));
+        assert!(comparisons < 11_000, "comparisons = {}", comparisons);
+    }
+
+    #[test]
+    fn min_run_range() {
+        assert_eq!(min_run(63), 63);
+        assert_eq!(min_run(64), 32);
+        assert_eq!(min_run(65), 33);
+        assert_eq!(min_run(2048), 32);
+        assert_eq!(min_run(2049), 33);
+
+        for n in 64..5000 {
+            assert!((32..=64).contains(&min_run(n)));
+        }
+    }
+}
@@ -0,0 +1,433 @@
+use std::cmp::Ordering;
+
+type Link<K, V> = Option<Box<Node<K, V>>>;
+
+struct Node<K, V> {
+    key: K,
+    value: V,
+    height: usize,
+    left: Link<K, V>,
+    right: Link<K, V>,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct AvlStats {
+    pub single_rotations: usize,
+    pub double_rotations: usize,
+}
+
+pub struct AvlTree<K, V> {
+    root: Link<K, V>,
+    len: usize,
+    stats: AvlStats,
+}
+
+impl<K, V> AvlTree<K, V> {
+    pub fn new() -> Self {
+        Self {
+            root: None,
+            len: 0,
+            stats: AvlStats::default(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn height(&self) -> usize {
+        height(&self.root)
+    }
+
+    pub fn stats(&self) -> AvlStats {
+        self.stats
+    }
+
+    pub fn inorder_walk(&self) -> Vec<(&K, &V)> {
+        let mut v = Vec::with_capacity(self.len);
+        let mut stack = Vec::new();
+        let mut curr = self.root.as_deref();
+
+        while curr.is_some() || !stack.is_empty() {
+            while let Some(node) = curr {
+                stack.push(node);
+                curr = node.left.as_deref();
+            }
+
+            let node = stack.pop().unwrap();
+            v.push((&node.key, &node.value));
+            curr = node.right.as_deref();
+        }
+
+        v
+    }
+
+    pub fn min_iter(&self) -> Option<(&K, &V)> {
+        let mut node = self.root.as_deref()?;
+
+        while let Some(left) = &node.left {
+            node = left;
+        }
+
+        Some((&node.key, &node.value))
+    }
+
+    pub fn max_iter(&self) -> Option<(&K, &V)> {
+        let mut node = self.root.as_deref()?;
+
+        while let Some(right) = &node.right {
+            node = right;
+        }
+
+        Some((&node.key, &node.value))
+    }
+}
+
+impl<K: Ord, V> AvlTree<K, V> {
+    pub fn search_iter(&self, key: &K) -> Option<&V> {
+        let mut node = self.root.as_deref()?;
+
+        loop {
+            match key.cmp(&node.key) {
+                Ordering::Less => node = node.left.as_deref()?,
+                Ordering::Greater => node = node.right.as_deref()?,
+                Ordering::Equal => return Some(&node.value),
+            }
+        }
+    }
+
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.search_iter(key).is_some()
+    }
+
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let (root, old) = insert(self.root.take(), key, value, &mut self.stats);
+        self.root = Some(root);
+
+        if old.is_none() {
+            self.len += 1;
+        }
+
+        old
+    }
+
+    pub fn delete(&mut self, key: &K) -> Option<V> {
+        let (root, removed) = delete(self.root.take(), key, &mut self.stats);
+        self.root = root;
+
+        if removed.is_some() {
+            self.len -= 1;
+        }
+
+        removed
+    }
+}
+
+impl<K, V> Default for AvlTree<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn height<K, V>(link: &Link<K, V>) -> usize {
+    link.as_ref().map_or(0, |node| node.height)
+}
+
+fn update<K, V>(node: &mut Node<K, V>) {
+    node.height = 1 + height(&node.left).max(height(&node.right));
+}
+
+fn rotate_left<K, V>(mut x: Box<Node<K, V>>) -> Box<Node<K, V>> {
+    let mut y = x.right.take().expect("rotate_left without right child");
+    x.right = y.left.take();
+    update(&mut x);
+    y.left = Some(x);
+    update(&mut y);
+    y
+}
+
+fn rotate_right<K, V>(mut x: Box<Node<K, V>>) -> Box<Node<K, V>> {
+    let mut y = x.left.take().expect("rotate_right without left child");
+    x.left = y.right.take();
+    update(&mut x);
+    y.right = Some(x);
+    update(&mut y);
+    y
+}
+
+fn rebalance<K, V>(mut node: Box<Node<K, V>>, stats: &mut AvlStats) -> Box<Node<K, V>> {
+    update(&mut node);
+
+    let lh = height(&node.left);
+    let rh = height(&node.right);
+
+    if lh > rh + 1 {
+        let left = node.left.take().unwrap();
+
+        if height(&left.left) < height(&left.right) {
+            node.left = Some(rotate_left(left));
+            stats.double_rotations += 1;
+        } else {
+            node.left = Some(left);
+            stats.single_rotations += 1;
+        }
+
+        rotate_right(node)
+    } else if rh > lh + 1 {
+        let right = node.right.take().unwrap();
+
+        if height(&right.right) < height(&right.left) {
+            node.right = Some(rotate_right(right));
+            stats.double_rotations += 1;
+        } else {
+            node.right = Some(right);
+            stats.single_rotations += 1;
+        }
+
+        rotate_left(node)
+    } else {
+        node
+    }
+}
+
+fn insert<K: Ord, V>(
+    link: Link<K, V>,
+    key: K,
+    value: V,
+    stats: &mut AvlStats,
+) -> (Box<Node<K, V>>, Option<V>) {
+    let Some(mut node) = link else {
+        let node = Box::new(Node {
+            key,
+            value,
+            height: 1,
+            left: None,
+            right: None,
+        });
+
+        return (node, None);
+    };
+
+    let old = match key.cmp(&node.key) {
+        Ordering::Less => {
+            let (left, old) = insert(node.left.take(), key, value, stats);
+            node.left = Some(left);
+            old
+        }
+        Ordering::Greater => {
+            let (right, old) = insert(node.right.take(), key, value, stats);
+            node.right = Some(right);
+            old
+        }
+        Ordering::Equal => {
+            let old = std::mem::replace(&mut node.value, value);
+            return (node, Some(old));
+        }
+    };
+
+    (rebalance(node, stats), old)
+}
+
+fn delete<K: Ord, V>(link: Link<K, V>, key: &K, stats: &mut AvlStats) -> (Link<K, V>, Option<V>) {
+    let Some(mut node) = link else {
+        return (None, None);
+    };
+
+    let removed = match key.cmp(&node.key) {
+        Ordering::Less => {
+            let (left, removed) = delete(node.left.take(), key, stats);
+            node.left = left;
+            removed
+        }
+        Ordering::Greater => {
+            let (right, removed) = delete(node.right.take(), key, stats);
+            node.right = right;
+            removed
+        }
+        Ordering::Equal => {
+            let Node {
+                value, left, right, ..
+            } = *node;
+
+            let root = match (left, right) {
+                (None, child) | (child, None) => child,
+                (Some(left), Some(right)) => {
+                    let (rest, mut succ) = delete_min(right, stats);
+                    succ.left = Some(left);
+                    succ.right = rest;
+                    Some(rebalance(succ, stats))
+                }
+            };
+
+            return (root, Some(value));
+        }
+    };
+
+    (Some(rebalance(node, stats)), removed)
+}
+
+// Detaches the minimum node of the subtree, returning what is left of the
+// subtree and the detached node.
+fn delete_min<K, V>(
+    mut node: Box<Node<K, V>>,
+    stats: &mut AvlStats,
+) -> (Link<K, V>, Box<Node<K, V>>) {
+    match node.left.take() {
+        None => (node.right.take(), node),
+        Some(left) => {
+            let (rest, min) = delete_min(left, stats);
+            node.left = rest;
+            (Some(rebalance(node, stats)), min)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check<K: Ord, V>(link: &Link<K, V>) -> usize {
+        let Some(node) = link else {
+            return 0;
+        };
+
+        if let Some(left) = &node.left {
+            assert!(left.key < node.key);
+        }
+
+        if let Some(right) = &node.right {
+            assert!(right.key > node.key);
+        }
+
+        let lh = check(&node.left);
+        let rh = check(&node.right);
+
+        assert!(lh.abs_diff(rh) <= 1, "unbalanced node");
+        assert_eq!(node.height, 1 + lh.max(rh));
+
+        node.height
+    }
+
+    fn new_tree() -> AvlTree<usize, &'static str> {
+        let mut tree = AvlTree::new();
+
+        for (key, value) in [(6, "f"), (4, "d"), (7, "g"), (2, "b"), (5, "e"), (8, "h")] {
+            tree.insert(key, value);
+        }
+
+        tree
+    }
+
+    #[test]
+    fn inorder() {
+        let tree = new_tree();
+        let keys: Vec<_> = tree.inorder_walk().into_iter().map(|(k, _)| *k).collect();
+        assert_eq!(keys, [2, 4, 5, 6, 7, 8]);
+    }
+
+    #[test]
+    fn search() {
+        let mut tree = new_tree();
+
+        assert_eq!(tree.search_iter(&4), Some(&"d"));
+        assert_eq!(tree.search_iter(&3), None);
+        assert!(tree.contains_key(&8));
+
+        assert_eq!(tree.insert(4, "D"), Some("d"));
+        assert_eq!(tree.search_iter(&4), Some(&"D"));
+        assert_eq!(tree.len(), 6);
+    }
+
+    #[test]
+    fn min_max() {
+        let tree = new_tree();
+        assert_eq!(tree.min_iter(), Some((&2, &"b")));
+        assert_eq!(tree.max_iter(), Some((&8, &"h")));
+
+        let empty: AvlTree<usize, ()> = AvlTree::new();
+        assert_eq!(empty.min_iter(), None);
+        assert_eq!(empty.max_iter(), None);
+    }
+
+    #[test]
+    fn rotations() {
+        let mut tree = AvlTree::new();
+
+        // 3, 2, 1 is a left-left case.
+        for key in [3, 2, 1] {
+            tree.insert(key, ());
+        }
+
+        assert_eq!(
+            tree.stats(),
+            AvlStats {
+                single_rotations: 1,
+                double_rotations: 0
+            }
+        );
+
+        // 1, 3, 2 is a right-left case.
+        let mut tree = AvlTree::new();
+
+        for key in [1, 3, 2] {
+            tree.insert(key, ());
+        }
+
+        assert_eq!(
+            tree.stats(),
+            AvlStats {
+                single_rotations: 0,
+                double_rotations: 1
+            }
+        );
+        check(&tree.root);
+    }
+
+    #[test]
+    fn sorted_input_stays_balanced() {
+        let mut tree = AvlTree::new();
+
+        for key in 0..(1 << 12) {
+            tree.insert(key, ());
+        }
+
+        check(&tree.root);
+        assert_eq!(tree.height(), 13);
+        assert_eq!(tree.stats().double_rotations, 0);
+    }
+
+    #[test]
+    fn delete() {
+        let mut tree = AvlTree::new();
+
+        for i in 0..1000usize {
+            tree.insert((i * 7919) % 1000, i);
+        }
+
+        for i in 0..1000usize {
+            let key = (i * 6007) % 1000;
+
+            if key % 3 != 0 {
+                assert!(tree.delete(&key).is_some());
+                assert_eq!(tree.delete(&key), None);
+            }
+        }
+
+        check(&tree.root);
+        assert_eq!(tree.len(), 334);
+
+        let keys: Vec<_> = tree.inorder_walk().into_iter().map(|(k, _)| *k).collect();
+        assert!(keys.into_iter().eq((0..1000).step_by(3)));
+
+        for key in (0..1000).step_by(3) {
+            assert!(tree.delete(&key).is_some());
+        }
+
+        assert!(tree.is_empty());
+        assert_eq!(tree.height(), 0);
+    }
+}
@@ -1,8 +1,12 @@
+use std::collections::VecDeque;
+use std::ops::{Bound, RangeBounds};
+
 pub struct Node<T> {
     value: T,
     // parent: Option<Box<Node<T>>>,
     left: Option<Box<Node<T>>>,
     right: Option<Box<Node<T>>>,
+    size: usize,
 }
 
 impl<T> Node<T>
@@ -15,23 +19,12 @@ where
             // parent: None,
             left: None,
             right: None,
+            size: 1,
         }
     }
 
     pub fn inorder_walk(&self) -> Vec<&T> {
-        let mut v = Vec::new();
-
-        if let Some(left) = &self.left {
-            v.append(&mut left.inorder_walk());
-        }
-
-        v.push(&self.value);
-
-        if let Some(right) = &self.right {
-            v.append(&mut right.inorder_walk());
-        }
-
-        v
+        self.inorder_iter().collect()
     }
 
     pub fn min_rec(&self) -> &T {
@@ -94,24 +87,623 @@ where
         }
     }
 
-    pub fn succ(&self) -> &T {
-        todo!()
-        // if let Some(right) = self.right {
-        //     right.min_iter()
-        // } else {
-        //     let mut node = self.parent;
-        //     while let Some(parent) = node && self
-        // }
+    // Smallest value in this subtree that is greater than `value`. The
+    // value itself does not have to be present.
+    pub fn succ(&self, value: &T) -> Option<&T> {
+        let mut node = Some(self);
+        let mut ancestor = None;
+
+        while let Some(curr) = node {
+            if *value < curr.value {
+                ancestor = Some(&curr.value);
+                node = curr.left.as_deref();
+            } else if *value > curr.value {
+                node = curr.right.as_deref();
+            } else {
+                return match &curr.right {
+                    Some(right) => Some(right.min_iter()),
+                    None => ancestor,
+                };
+            }
+        }
+
+        ancestor
+    }
+
+    // Largest value in this subtree that is less than `value`.
+    pub fn pred(&self, value: &T) -> Option<&T> {
+        let mut node = Some(self);
+        let mut ancestor = None;
+
+        while let Some(curr) = node {
+            if *value > curr.value {
+                ancestor = Some(&curr.value);
+                node = curr.right.as_deref();
+            } else if *value < curr.value {
+                node = curr.left.as_deref();
+            } else {
+                return match &curr.left {
+                    Some(left) => Some(left.max_iter()),
+                    None => ancestor,
+                };
+            }
+        }
+
+        ancestor
+    }
+
+    // k-th smallest value in this subtree, counting from zero.
+    pub fn select(&self, mut k: usize) -> Option<&T> {
+        let mut node = self;
+
+        loop {
+            let left = size(&node.left);
+
+            if k < left {
+                node = node.left.as_deref()?;
+            } else if k > left {
+                k -= left + 1;
+                node = node.right.as_deref()?;
+            } else {
+                return Some(&node.value);
+            }
+        }
+    }
+
+    // Number of values in this subtree that are less than `value`.
+    pub fn rank(&self, value: &T) -> usize {
+        self.count_below(value, false)
+    }
+
+    pub fn count_range<R: RangeBounds<T>>(&self, range: R) -> usize {
+        let low = match range.start_bound() {
+            Bound::Included(start) => self.count_below(start, false),
+            Bound::Excluded(start) => self.count_below(start, true),
+            Bound::Unbounded => 0,
+        };
+
+        let high = match range.end_bound() {
+            Bound::Included(end) => self.count_below(end, true),
+            Bound::Excluded(end) => self.count_below(end, false),
+            Bound::Unbounded => self.size,
+        };
+
+        high.saturating_sub(low)
+    }
+
+    fn count_below(&self, value: &T, inclusive: bool) -> usize {
+        let mut count = 0;
+        let mut node = Some(self);
+
+        while let Some(curr) = node {
+            if *value < curr.value {
+                node = curr.left.as_deref();
+            } else if *value > curr.value {
+                count += size(&curr.left) + 1;
+                node = curr.right.as_deref();
+            } else {
+                return count + size(&curr.left) + inclusive as usize;
+            }
+        }
+
+        count
+    }
+
+    // Checks the BST invariant (strictly, as `BinarySearchTree` keeps no
+    // duplicates) and returns the first node, in preorder, that lies
+    // outside the bounds set by its ancestors.
+    pub fn validate(&self) -> Result<(), &Node<T>> {
+        let mut stack: Vec<(&Node<T>, Option<&T>, Option<&T>)> = vec![(self, None, None)];
+
+        while let Some((node, low, high)) = stack.pop() {
+            let above = low.is_none_or(|low| node.value > *low);
+            let below = high.is_none_or(|high| node.value < *high);
+
+            if !(above && below) {
+                return Err(node);
+            }
+
+            if let Some(right) = node.right.as_deref() {
+                stack.push((right, Some(&node.value), high));
+            }
+
+            if let Some(left) = node.left.as_deref() {
+                stack.push((left, low, Some(&node.value)));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl<T> Node<T> {
+    pub fn value(&self) -> &T {
+        &self.value
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    fn update_size(&mut self) {
+        self.size = 1 + size(&self.left) + size(&self.right);
+    }
+
+    pub fn inorder_iter(&self) -> InorderIter<'_, T> {
+        InorderIter::new(Some(self))
+    }
+
+    pub fn preorder_iter(&self) -> PreorderIter<'_, T> {
+        PreorderIter::new(Some(self))
+    }
+
+    pub fn postorder_iter(&self) -> PostorderIter<'_, T> {
+        PostorderIter::new(Some(self))
+    }
+
+    pub fn level_order_iter(&self) -> LevelOrderIter<'_, T> {
+        LevelOrderIter::new(Some(self))
+    }
+
+    pub fn height(&self) -> usize {
+        let mut height = 0;
+        let mut stack = vec![(self, 1)];
+
+        while let Some((node, depth)) = stack.pop() {
+            height = height.max(depth);
+            stack.extend(node.left.as_deref().map(|left| (left, depth + 1)));
+            stack.extend(node.right.as_deref().map(|right| (right, depth + 1)));
+        }
+
+        height
+    }
+}
+
+pub struct BinarySearchTree<T> {
+    root: Option<Box<Node<T>>>,
+    len: usize,
+}
+
+impl<T> BinarySearchTree<T> {
+    pub fn new() -> Self {
+        Self { root: None, len: 0 }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn height(&self) -> usize {
+        self.root.as_ref().map_or(0, |root| root.height())
+    }
+
+    pub fn root(&self) -> Option<&Node<T>> {
+        self.root.as_deref()
+    }
+
+    pub fn iter(&self) -> InorderIter<'_, T> {
+        InorderIter::new(self.root.as_deref())
+    }
+}
+
+impl<T> BinarySearchTree<T>
+where
+    T: PartialEq + PartialOrd,
+{
+    // Counts the new node into every size on the way down, and walks the
+    // same path again only to take that back if `value` is already present.
+    pub fn insert(&mut self, value: T) -> bool {
+        let mut link = &mut self.root;
+
+        while let Some(node) = link {
+            if value == node.value {
+                resize_path(&mut self.root, &value, false);
+                return false;
+            }
+
+            node.size += 1;
+            link = if value < node.value {
+                &mut node.left
+            } else {
+                &mut node.right
+            };
+        }
+
+        *link = Some(Box::new(Node::new(value)));
+        self.len += 1;
+        true
+    }
+
+    // CLRS TREE-DELETE. With owned links, TRANSPLANT(u, v) is simply
+    // overwriting the link that holds `u` with `v`.
+    pub fn delete(&mut self, value: &T) -> Option<T> {
+        let link = find_link(&mut self.root, value);
+
+        let Some(mut z) = link.take() else {
+            resize_path(&mut self.root, value, true);
+            return None;
+        };
+
+        *link = match (z.left.take(), z.right.take()) {
+            (None, right) => right,
+            (left, None) => left,
+            (left, Some(right)) => {
+                let mut right = Some(right);
+                let mut y = take_min(&mut right);
+                y.left = left;
+                y.right = right;
+                y.update_size();
+                Some(y)
+            }
+        };
+
+        self.len -= 1;
+        Some(z.value)
+    }
+
+    pub fn contains(&self, value: &T) -> bool {
+        let mut node = self.root.as_deref();
+
+        while let Some(curr) = node {
+            if *value == curr.value {
+                return true;
+            }
+
+            node = if *value < curr.value {
+                curr.left.as_deref()
+            } else {
+                curr.right.as_deref()
+            };
+        }
+
+        false
+    }
+
+    pub fn min(&self) -> Option<&T> {
+        self.root.as_ref().map(|root| root.min_iter())
+    }
+
+    pub fn max(&self) -> Option<&T> {
+        self.root.as_ref().map(|root| root.max_iter())
+    }
+
+    pub fn successor(&self, value: &T) -> Option<&T> {
+        self.root.as_ref()?.succ(value)
+    }
+
+    pub fn predecessor(&self, value: &T) -> Option<&T> {
+        self.root.as_ref()?.pred(value)
+    }
+
+    pub fn select(&self, k: usize) -> Option<&T> {
+        self.root.as_ref()?.select(k)
+    }
+
+    pub fn rank(&self, value: &T) -> usize {
+        self.root.as_ref().map_or(0, |root| root.rank(value))
+    }
+
+    pub fn count_range<R: RangeBounds<T>>(&self, range: R) -> usize {
+        self.root.as_ref().map_or(0, |root| root.count_range(range))
+    }
+}
+
+fn size<T>(link: &Option<Box<Node<T>>>) -> usize {
+    link.as_ref().map_or(0, |node| node.size)
+}
+
+// Returns the link holding `value`, or the empty link where it would go.
+// Every node passed on the way is counted as losing one descendant, which
+// the caller must undo if `value` turns out to be missing.
+fn find_link<'a, T: PartialEq + PartialOrd>(
+    mut link: &'a mut Option<Box<Node<T>>>,
+    value: &T,
+) -> &'a mut Option<Box<Node<T>>> {
+    loop {
+        let go_left = match link.as_deref() {
+            Some(node) if *value != node.value => *value < node.value,
+            _ => return link,
+        };
+
+        let node = link.as_mut().unwrap();
+        node.size -= 1;
+        link = if go_left {
+            &mut node.left
+        } else {
+            &mut node.right
+        };
+    }
+}
+
+// Undoes the size changes made while searching for `value`: grows or
+// shrinks by one every node above the one holding it, or every node on the
+// path if it is missing.
+fn resize_path<T: PartialEq + PartialOrd>(
+    mut link: &mut Option<Box<Node<T>>>,
+    value: &T,
+    grow: bool,
+) {
+    while let Some(node) = link {
+        if *value == node.value {
+            return;
+        }
+
+        if grow {
+            node.size += 1;
+        } else {
+            node.size -= 1;
+        }
+
+        link = if *value < node.value {
+            &mut node.left
+        } else {
+            &mut node.right
+        };
+    }
+}
+
+// Unlinks the minimum node of a non-empty subtree, splicing its right child
+// into its place.
+fn take_min<T>(mut link: &mut Option<Box<Node<T>>>) -> Box<Node<T>> {
+    while link.as_ref().unwrap().left.is_some() {
+        let node = link.as_mut().unwrap();
+        node.size -= 1;
+        link = &mut node.left;
+    }
+
+    let mut node = link.take().unwrap();
+    *link = node.right.take();
+    node
+}
+
+impl<T> Default for BinarySearchTree<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Degenerate trees are as deep as they are long, so tear them down without
+// recursion.
+impl<T> Drop for BinarySearchTree<T> {
+    fn drop(&mut self) {
+        let mut stack = Vec::from_iter(self.root.take());
+
+        while let Some(mut node) = stack.pop() {
+            stack.extend(node.left.take());
+            stack.extend(node.right.take());
+        }
+    }
+}
+
+impl<'a, T> IntoIterator for &'a BinarySearchTree<T> {
+    type Item = &'a T;
+    type IntoIter = InorderIter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+// The front stack holds the left spine still to be visited, the back stack
+// the right spine. Both ends remember the node they yielded last so they can
+// tell when they have met.
+pub struct InorderIter<'a, T> {
+    front: Vec<&'a Node<T>>,
+    back: Vec<&'a Node<T>>,
+    last_front: Option<&'a Node<T>>,
+    last_back: Option<&'a Node<T>>,
+}
+
+impl<'a, T> InorderIter<'a, T> {
+    fn new(root: Option<&'a Node<T>>) -> Self {
+        let mut iter = Self {
+            front: Vec::new(),
+            back: Vec::new(),
+            last_front: None,
+            last_back: None,
+        };
+
+        iter.push_left(root);
+        iter.push_right(root);
+        iter
+    }
+
+    fn push_left(&mut self, mut node: Option<&'a Node<T>>) {
+        while let Some(curr) = node {
+            self.front.push(curr);
+            node = curr.left.as_deref();
+        }
+    }
+
+    fn push_right(&mut self, mut node: Option<&'a Node<T>>) {
+        while let Some(curr) = node {
+            self.back.push(curr);
+            node = curr.right.as_deref();
+        }
+    }
+
+    fn finish(&mut self) {
+        self.front.clear();
+        self.back.clear();
+    }
+}
+
+impl<'a, T> Iterator for InorderIter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.front.pop()?;
+
+        if self.last_back.is_some_and(|last| std::ptr::eq(last, node)) {
+            self.finish();
+            return None;
+        }
+
+        self.push_left(node.right.as_deref());
+        self.last_front = Some(node);
+        Some(&node.value)
+    }
+}
+
+impl<'a, T> DoubleEndedIterator for InorderIter<'a, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let node = self.back.pop()?;
+
+        if self.last_front.is_some_and(|last| std::ptr::eq(last, node)) {
+            self.finish();
+            return None;
+        }
+
+        self.push_right(node.left.as_deref());
+        self.last_back = Some(node);
+        Some(&node.value)
+    }
+}
+
+pub struct PreorderIter<'a, T> {
+    stack: Vec<&'a Node<T>>,
+}
+
+impl<'a, T> PreorderIter<'a, T> {
+    fn new(root: Option<&'a Node<T>>) -> Self {
+        Self {
+            stack: Vec::from_iter(root),
+        }
+    }
+}
+
+impl<'a, T> Iterator for PreorderIter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.stack.pop()?;
+        self.stack.extend(node.right.as_deref());
+        self.stack.extend(node.left.as_deref());
+        Some(&node.value)
     }
+}
 
-    pub fn pred(&self) {}
+// A node is yielded the second time it is popped, once both of its
+// subtrees have been.
+pub struct PostorderIter<'a, T> {
+    stack: Vec<(&'a Node<T>, bool)>,
+}
+
+impl<'a, T> PostorderIter<'a, T> {
+    fn new(root: Option<&'a Node<T>>) -> Self {
+        Self {
+            stack: Vec::from_iter(root.map(|root| (root, false))),
+        }
+    }
 }
 
-// impl<T> From<&[T]> for Node<T> {
-//     fn from(value: &[T]) -> Node<T> {
-//         todo!();
-//     }
-// }
+impl<'a, T> Iterator for PostorderIter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some((node, expanded)) = self.stack.pop() {
+            if expanded {
+                return Some(&node.value);
+            }
+
+            self.stack.push((node, true));
+            self.stack
+                .extend(node.right.as_deref().map(|right| (right, false)));
+            self.stack
+                .extend(node.left.as_deref().map(|left| (left, false)));
+        }
+
+        None
+    }
+}
+
+pub struct LevelOrderIter<'a, T> {
+    queue: VecDeque<&'a Node<T>>,
+}
+
+impl<'a, T> LevelOrderIter<'a, T> {
+    fn new(root: Option<&'a Node<T>>) -> Self {
+        Self {
+            queue: VecDeque::from_iter(root),
+        }
+    }
+}
+
+impl<'a, T> Iterator for LevelOrderIter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.queue.pop_front()?;
+        self.queue.extend(node.left.as_deref());
+        self.queue.extend(node.right.as_deref());
+        Some(&node.value)
+    }
+}
+
+// Builds a perfectly balanced tree from the next `n` values of an in-order
+// sequence.
+fn build_balanced<T: PartialEq + PartialOrd, I: Iterator<Item = T>>(
+    iter: &mut I,
+    n: usize,
+) -> Option<Box<Node<T>>> {
+    if n == 0 {
+        return None;
+    }
+
+    let left = build_balanced(iter, n / 2);
+    let mut node = Node::new(iter.next().expect("iterator shorter than n"));
+    node.left = left;
+    node.right = build_balanced(iter, n - n / 2 - 1);
+    node.size = n;
+
+    Some(Box::new(node))
+}
+
+impl<T> Extend<T> for BinarySearchTree<T>
+where
+    T: PartialEq + PartialOrd,
+{
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for value in iter {
+            self.insert(value);
+        }
+    }
+}
+
+// Sorted input, duplicates and all, is built into a height-balanced tree;
+// anything else falls back to inserting values one by one.
+impl<T> FromIterator<T> for BinarySearchTree<T>
+where
+    T: PartialEq + PartialOrd,
+{
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut values: Vec<T> = iter.into_iter().collect();
+        let mut tree = BinarySearchTree::new();
+
+        if values.windows(2).all(|w| w[0] <= w[1]) {
+            values.dedup();
+            tree.len = values.len();
+            tree.root = build_balanced(&mut values.into_iter(), tree.len);
+        } else {
+            tree.extend(values);
+        }
+
+        tree
+    }
+}
+
+impl<T> From<&[T]> for BinarySearchTree<T>
+where
+    T: PartialEq + PartialOrd + Clone,
+{
+    fn from(values: &[T]) -> Self {
+        values.iter().cloned().collect()
+    }
+}
 
 #[cfg(test)]
 mod tests {
@@ -132,15 +724,94 @@ mod tests {
         root.left = Some(Box::new(left));
         root.right = Some(Box::new(right));
 
+        fix_sizes(&mut root);
         root
     }
 
+    fn fix_sizes(node: &mut Node<usize>) {
+        if let Some(left) = node.left.as_mut() {
+            fix_sizes(left);
+        }
+
+        if let Some(right) = node.right.as_mut() {
+            fix_sizes(right);
+        }
+
+        node.update_size();
+    }
+
+    // Checks every subtree size against a recount.
+    fn check_sizes<T>(link: &Option<Box<Node<T>>>) -> usize {
+        let Some(node) = link else {
+            return 0;
+        };
+
+        let size = 1 + check_sizes(&node.left) + check_sizes(&node.right);
+        assert_eq!(node.size, size);
+        size
+    }
+
     #[test]
     fn inorder() {
         let root = new_tree();
         assert_eq!(root.inorder_walk(), [&2, &4, &5, &6, &7, &8]);
     }
 
+    #[test]
+    fn traversals() {
+        let root = new_tree();
+
+        assert!(root.inorder_iter().eq([&2, &4, &5, &6, &7, &8]));
+        assert!(root.preorder_iter().eq([&6, &4, &2, &5, &7, &8]));
+        assert!(root.postorder_iter().eq([&2, &5, &4, &8, &7, &6]));
+        assert!(root.level_order_iter().eq([&6, &4, &7, &2, &5, &8]));
+
+        let leaf = Node::new(1);
+        assert!(leaf.inorder_iter().eq([&1]));
+        assert!(leaf.postorder_iter().eq([&1]));
+    }
+
+    #[test]
+    fn inorder_double_ended() {
+        let root = new_tree();
+
+        assert!(root.inorder_iter().rev().eq([&8, &7, &6, &5, &4, &2]));
+
+        let mut iter = root.inorder_iter();
+        assert_eq!(iter.next(), Some(&2));
+        assert_eq!(iter.next_back(), Some(&8));
+        assert_eq!(iter.next_back(), Some(&7));
+        assert_eq!(iter.next(), Some(&4));
+        assert_eq!(iter.next(), Some(&5));
+        assert_eq!(iter.next_back(), Some(&6));
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.next_back(), None);
+
+        for split in 0..=6 {
+            let mut iter = root.inorder_iter();
+            let mut v: Vec<_> = iter.by_ref().take(split).collect();
+            let mut tail: Vec<_> = iter.rev().collect();
+            tail.reverse();
+            v.extend(tail);
+            assert_eq!(v, [&2, &4, &5, &6, &7, &8]);
+        }
+    }
+
+    #[test]
+    fn bst_iter_deep() {
+        let mut tree = BinarySearchTree::new();
+
+        for value in (0..10_000).rev() {
+            tree.insert(value);
+        }
+
+        assert!(tree.iter().copied().eq(0..10_000));
+        assert!(tree.iter().rev().copied().eq((0..10_000).rev()));
+        assert_eq!(tree.root().unwrap().postorder_iter().count(), 10_000);
+        assert_eq!((&tree).into_iter().next(), Some(&0));
+        assert_eq!(BinarySearchTree::<u8>::new().iter().next(), None);
+    }
+
     #[test]
     fn search() {
         let root = new_tree();
@@ -163,4 +834,281 @@ mod tests {
         assert_eq!(root.min_iter(), &2);
         assert_eq!(root.max_iter(), &8);
     }
+
+    #[test]
+    fn succ_pred() {
+        let root = new_tree();
+
+        assert_eq!(root.succ(&2), Some(&4));
+        assert_eq!(root.succ(&5), Some(&6));
+        assert_eq!(root.succ(&6), Some(&7));
+        assert_eq!(root.succ(&3), Some(&4));
+        assert_eq!(root.succ(&8), None);
+
+        assert_eq!(root.pred(&8), Some(&7));
+        assert_eq!(root.pred(&6), Some(&5));
+        assert_eq!(root.pred(&7), Some(&6));
+        assert_eq!(root.pred(&3), Some(&2));
+        assert_eq!(root.pred(&2), None);
+    }
+
+    fn new_bst() -> BinarySearchTree<usize> {
+        let mut tree = BinarySearchTree::new();
+
+        for value in [15, 6, 18, 3, 7, 17, 20, 2, 4, 13, 9] {
+            assert!(tree.insert(value));
+        }
+
+        tree
+    }
+
+    #[test]
+    fn bst_insert() {
+        let mut tree = new_bst();
+
+        assert_eq!(tree.len(), 11);
+        assert_eq!(tree.height(), 5);
+        assert!(!tree.insert(7));
+        assert_eq!(tree.len(), 11);
+
+        assert!(tree.contains(&13));
+        assert!(!tree.contains(&14));
+        assert_eq!(tree.min(), Some(&2));
+        assert_eq!(tree.max(), Some(&20));
+
+        let root = tree.root().unwrap();
+        assert_eq!(
+            root.inorder_walk(),
+            [&2, &3, &4, &6, &7, &9, &13, &15, &17, &18, &20]
+        );
+        assert_eq!(root.search_iter(6).unwrap().value(), &6);
+    }
+
+    #[test]
+    fn bst_succ_pred() {
+        let tree = new_bst();
+
+        assert_eq!(tree.successor(&15), Some(&17));
+        assert_eq!(tree.successor(&13), Some(&15));
+        assert_eq!(tree.successor(&4), Some(&6));
+        assert_eq!(tree.successor(&20), None);
+
+        assert_eq!(tree.predecessor(&15), Some(&13));
+        assert_eq!(tree.predecessor(&6), Some(&4));
+        assert_eq!(tree.predecessor(&17), Some(&15));
+        assert_eq!(tree.predecessor(&2), None);
+
+        let empty: BinarySearchTree<usize> = BinarySearchTree::new();
+        assert_eq!(empty.successor(&1), None);
+        assert_eq!(empty.predecessor(&1), None);
+    }
+
+    #[test]
+    fn bst_delete() {
+        let mut tree = new_bst();
+
+        // Leaf, one child, two children with the successor deeper down, and
+        // finally the root.
+        assert_eq!(tree.delete(&9), Some(9));
+        assert_eq!(tree.delete(&7), Some(7));
+        assert_eq!(tree.delete(&6), Some(6));
+        assert_eq!(tree.delete(&15), Some(15));
+        assert_eq!(tree.delete(&15), None);
+        assert_eq!(tree.delete(&100), None);
+
+        assert_eq!(tree.len(), 7);
+        let root = tree.root().unwrap();
+        assert_eq!(root.value(), &17);
+        assert_eq!(root.inorder_walk(), [&2, &3, &4, &13, &17, &18, &20]);
+
+        for value in [2, 3, 4, 13, 17, 18, 20] {
+            assert_eq!(tree.delete(&value), Some(value));
+        }
+
+        assert!(tree.is_empty());
+        assert_eq!(tree.height(), 0);
+        assert!(tree.root().is_none());
+    }
+
+    #[test]
+    fn validate() {
+        let mut root = new_tree();
+        assert!(root.validate().is_ok());
+
+        // 3 sits in the right subtree of 4, which only holds values above 4.
+        root.left.as_mut().unwrap().right.as_mut().unwrap().left = Some(Box::new(Node::new(3)));
+        assert_eq!(root.validate().unwrap_err().value(), &3);
+
+        let mut root = new_tree();
+        root.right.as_mut().unwrap().value = 6;
+        assert_eq!(root.validate().unwrap_err().value(), &6);
+    }
+
+    #[test]
+    fn from_sorted() {
+        let values: Vec<usize> = (0..1000).collect();
+        let tree = BinarySearchTree::from(&values[..]);
+
+        assert_eq!(tree.len(), 1000);
+        assert_eq!(tree.height(), 10);
+        assert!(tree.root().unwrap().validate().is_ok());
+        assert!(tree.iter().eq(values.iter()));
+
+        let tree: BinarySearchTree<usize> = (0..7).collect();
+        let root = tree.root().unwrap();
+        assert!(root.level_order_iter().eq([&3, &1, &5, &0, &2, &4, &6]));
+
+        // Duplicates are dropped, as `insert` would, without losing balance.
+        let values: Vec<usize> = (0..3000).map(|i| i / 3).collect();
+        let tree = BinarySearchTree::from(&values[..]);
+
+        assert_eq!(tree.len(), 1000);
+        assert_eq!(tree.height(), 10);
+        assert!(tree.iter().copied().eq(0..1000));
+        check_sizes(&tree.root);
+
+        let empty = BinarySearchTree::<usize>::from(&[][..]);
+        assert!(empty.is_empty());
+        assert!(empty.root().is_none());
+    }
+
+    #[test]
+    fn from_unsorted() {
+        let tree: BinarySearchTree<usize> = [6, 4, 7, 2, 5, 8, 4].into_iter().collect();
+
+        assert_eq!(tree.len(), 6);
+        assert!(tree.root().unwrap().validate().is_ok());
+        assert!(tree
+            .root()
+            .unwrap()
+            .preorder_iter()
+            .eq(new_tree().preorder_iter()));
+
+        let mut tree = BinarySearchTree::from(&[1, 3, 5][..]);
+        tree.extend([4, 2, 0]);
+        assert!(tree.iter().copied().eq(0..6));
+        assert!(tree.root().unwrap().validate().is_ok());
+    }
+
+    #[test]
+    fn select_rank() {
+        let root = new_tree();
+
+        assert_eq!(root.size(), 6);
+        assert_eq!(root.select(0), Some(&2));
+        assert_eq!(root.select(3), Some(&6));
+        assert_eq!(root.select(5), Some(&8));
+        assert_eq!(root.select(6), None);
+
+        assert_eq!(root.rank(&2), 0);
+        assert_eq!(root.rank(&6), 3);
+        assert_eq!(root.rank(&3), 1);
+        assert_eq!(root.rank(&100), 6);
+
+        assert_eq!(root.count_range(4..7), 3);
+        assert_eq!(root.count_range(4..=7), 4);
+        assert_eq!(root.count_range(3..), 5);
+        assert_eq!(root.count_range(..), 6);
+        assert_eq!(root.count_range(..=2), 1);
+        assert_eq!(root.count_range(9..20), 0);
+        assert_eq!(
+            root.count_range((Bound::Excluded(5), Bound::Excluded(4))),
+            0
+        );
+    }
+
+    #[test]
+    fn order_statistics_follow_updates() {
+        let mut tree = BinarySearchTree::new();
+
+        for i in 0..500usize {
+            tree.insert((i * 7919) % 500);
+        }
+
+        tree.insert(42);
+        check_sizes(&tree.root);
+
+        for i in 0..500usize {
+            let value = (i * 6007) % 500;
+
+            if value % 4 != 0 {
+                tree.delete(&value);
+            }
+        }
+
+        tree.delete(&1);
+        check_sizes(&tree.root);
+
+        let remaining: Vec<usize> = (0..500).step_by(4).collect();
+        assert_eq!(tree.len(), remaining.len());
+
+        for (k, value) in remaining.iter().enumerate() {
+            assert_eq!(tree.select(k), Some(value));
+            assert_eq!(tree.rank(value), k);
+            assert_eq!(tree.rank(&(value + 1)), k + 1);
+        }
+
+        assert_eq!(tree.select(remaining.len()), None);
+        assert_eq!(tree.count_range(100..200), 25);
+        assert_eq!(tree.count_range(101..=200), 25);
+
+        let tree: BinarySearchTree<usize> = (0..100).collect();
+        check_sizes(&tree.root);
+        assert_eq!(tree.select(37), Some(&37));
+    }
+
+    #[test]
+    fn updates_descend_once() {
+        use std::cell::Cell;
+        use std::cmp::Ordering;
+
+        thread_local! {
+            static COMPARISONS: Cell<usize> = const { Cell::new(0) };
+        }
+
+        // Counts every comparison made on it, `==` and `<` alike.
+        #[derive(Debug)]
+        struct Probe(u32);
+
+        impl PartialEq for Probe {
+            fn eq(&self, other: &Self) -> bool {
+                COMPARISONS.with(|c| c.set(c.get() + 1));
+                self.0 == other.0
+            }
+        }
+
+        impl PartialOrd for Probe {
+            fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+                COMPARISONS.with(|c| c.set(c.get() + 1));
+                self.0.partial_cmp(&other.0)
+            }
+        }
+
+        let counted = |f: &mut dyn FnMut()| {
+            COMPARISONS.with(|c| c.set(0));
+            f();
+            COMPARISONS.with(|c| c.get())
+        };
+
+        // Height 10: a single descent makes at most two comparisons a level.
+        let mut tree: BinarySearchTree<Probe> = (0..1023).map(|i| Probe(2 * i)).collect();
+        assert_eq!(tree.height(), 10);
+
+        assert!(counted(&mut || assert!(tree.insert(Probe(101)))) <= 2 * 11);
+        assert!(counted(&mut || assert!(tree.delete(&Probe(500)).is_some())) <= 2 * 11);
+        check_sizes(&tree.root);
+        assert_eq!(tree.len(), 1023);
+    }
+
+    #[test]
+    fn bst_degenerate() {
+        let mut tree = BinarySearchTree::new();
+
+        for value in 0..10_000 {
+            tree.insert(value);
+        }
+
+        assert_eq!(tree.height(), 10_000);
+        assert_eq!(tree.successor(&9_998), Some(&9_999));
+    }
 }
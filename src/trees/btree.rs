@@ -0,0 +1,661 @@
+use std::ops::{Bound, RangeBounds};
+
+// `B` is the order of the tree: every node has at most `B` children and
+// `B - 1` keys, and every node but the root at least `ceil(B / 2)` children.
+// A node is a leaf exactly when `children` is empty.
+struct Node<K, V> {
+    keys: Vec<K>,
+    vals: Vec<V>,
+    children: Vec<Node<K, V>>,
+}
+
+impl<K, V> Node<K, V> {
+    fn new(capacity: usize) -> Self {
+        Self {
+            keys: Vec::with_capacity(capacity),
+            vals: Vec::with_capacity(capacity),
+            children: Vec::new(),
+        }
+    }
+
+    fn is_leaf(&self) -> bool {
+        self.children.is_empty()
+    }
+}
+
+// Median and new right sibling handed up by a node that overflowed.
+type Split<K, V> = Option<(K, V, Node<K, V>)>;
+
+pub struct BTree<K, V, const B: usize> {
+    root: Node<K, V>,
+    len: usize,
+}
+
+impl<K, V, const B: usize> BTree<K, V, B> {
+    const MIN_KEYS: usize = B.div_ceil(2) - 1;
+
+    pub fn new() -> Self {
+        assert!(B >= 3, "B = {}", B);
+
+        Self {
+            root: Node::new(B),
+            len: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn height(&self) -> usize {
+        if self.len == 0 {
+            return 0;
+        }
+
+        let mut height = 1;
+        let mut node = &self.root;
+
+        while let Some(child) = node.children.first() {
+            height += 1;
+            node = child;
+        }
+
+        height
+    }
+
+    pub fn iter(&self) -> Range<'_, K, V> {
+        let mut iter = Range {
+            stack: Vec::new(),
+            end: None,
+        };
+
+        iter.descend(&self.root, |_| 0);
+        iter
+    }
+
+    // Builds the tree level by level from keys in strictly increasing order,
+    // packing every node as evenly as the invariants allow.
+    pub fn from_sorted<I: IntoIterator<Item = (K, V)>>(items: I) -> Self
+    where
+        K: Ord,
+    {
+        let items: Vec<(K, V)> = items.into_iter().collect();
+        assert!(
+            items.windows(2).all(|w| w[0].0 < w[1].0),
+            "keys are not strictly increasing"
+        );
+
+        let mut tree = Self::new();
+        let n = items.len();
+
+        if n == 0 {
+            return tree;
+        }
+
+        // Each leaf but the last hands one key up as a separator.
+        let leaves = (n + 1).div_ceil(B);
+        let mut items = items.into_iter();
+        let mut nodes = Vec::with_capacity(leaves);
+        let mut seps = Vec::with_capacity(leaves - 1);
+
+        for (j, count) in even_split(n - (leaves - 1), leaves).enumerate() {
+            let mut leaf = Node::new(B);
+
+            for (key, value) in items.by_ref().take(count) {
+                leaf.keys.push(key);
+                leaf.vals.push(value);
+            }
+
+            nodes.push(leaf);
+
+            if j + 1 < leaves {
+                seps.push(items.next().unwrap());
+            }
+        }
+
+        while nodes.len() > 1 {
+            let parents = nodes.len().div_ceil(B);
+            let mut children = nodes.into_iter();
+            let mut below = seps.into_iter();
+
+            nodes = Vec::with_capacity(parents);
+            seps = Vec::with_capacity(parents - 1);
+
+            for (j, count) in even_split(children.len(), parents).enumerate() {
+                let mut parent = Node::new(B);
+                parent.children.extend(children.by_ref().take(count));
+
+                for (key, value) in below.by_ref().take(count - 1) {
+                    parent.keys.push(key);
+                    parent.vals.push(value);
+                }
+
+                nodes.push(parent);
+
+                if j + 1 < parents {
+                    seps.push(below.next().unwrap());
+                }
+            }
+        }
+
+        tree.root = nodes.pop().unwrap();
+        tree.len = n;
+        tree
+    }
+
+    // Restores the minimum occupancy of `node.children[i]` after a removal,
+    // borrowing through the parent from a sibling that can spare a key, or
+    // merging with one that cannot.
+    fn fix_child(node: &mut Node<K, V>, i: usize) {
+        if node.children[i].keys.len() >= Self::MIN_KEYS {
+            return;
+        }
+
+        if i > 0 && node.children[i - 1].keys.len() > Self::MIN_KEYS {
+            let (before, after) = node.children.split_at_mut(i);
+            let left = &mut before[i - 1];
+            let child = &mut after[0];
+
+            let key = std::mem::replace(&mut node.keys[i - 1], left.keys.pop().unwrap());
+            let value = std::mem::replace(&mut node.vals[i - 1], left.vals.pop().unwrap());
+            child.keys.insert(0, key);
+            child.vals.insert(0, value);
+
+            if let Some(grandchild) = left.children.pop() {
+                child.children.insert(0, grandchild);
+            }
+        } else if i + 1 < node.children.len() && node.children[i + 1].keys.len() > Self::MIN_KEYS {
+            let (before, after) = node.children.split_at_mut(i + 1);
+            let child = &mut before[i];
+            let right = &mut after[0];
+
+            let key = std::mem::replace(&mut node.keys[i], right.keys.remove(0));
+            let value = std::mem::replace(&mut node.vals[i], right.vals.remove(0));
+            child.keys.push(key);
+            child.vals.push(value);
+
+            if !right.is_leaf() {
+                child.children.push(right.children.remove(0));
+            }
+        } else {
+            let j = if i > 0 { i - 1 } else { i };
+            let right = node.children.remove(j + 1);
+            let left = &mut node.children[j];
+
+            left.keys.push(node.keys.remove(j));
+            left.vals.push(node.vals.remove(j));
+            left.keys.extend(right.keys);
+            left.vals.extend(right.vals);
+            left.children.extend(right.children);
+        }
+    }
+
+    fn remove_max(node: &mut Node<K, V>) -> (K, V) {
+        if node.is_leaf() {
+            return (node.keys.pop().unwrap(), node.vals.pop().unwrap());
+        }
+
+        let last = node.children.len() - 1;
+        let max = Self::remove_max(&mut node.children[last]);
+        Self::fix_child(node, last);
+        max
+    }
+}
+
+impl<K: Ord, V, const B: usize> BTree<K, V, B> {
+    pub fn get(&self, key: &K) -> Option<&V> {
+        let mut node = &self.root;
+
+        loop {
+            match node.keys.binary_search(key) {
+                Ok(i) => return Some(&node.vals[i]),
+                Err(i) => node = node.children.get(i)?,
+            }
+        }
+    }
+
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        let mut node = &mut self.root;
+
+        loop {
+            match node.keys.binary_search(key) {
+                Ok(i) => return Some(&mut node.vals[i]),
+                Err(i) => node = node.children.get_mut(i)?,
+            }
+        }
+    }
+
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.get(key).is_some()
+    }
+
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let (old, split) = Self::insert_rec(&mut self.root, key, value);
+
+        if let Some((key, value, right)) = split {
+            let mut root = Node::new(B);
+            root.keys.push(key);
+            root.vals.push(value);
+            root.children
+                .push(std::mem::replace(&mut self.root, Node::new(B)));
+            root.children.push(right);
+            self.root = root;
+        }
+
+        if old.is_none() {
+            self.len += 1;
+        }
+
+        old
+    }
+
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let removed = Self::remove_rec(&mut self.root, key)?;
+
+        if self.root.keys.is_empty() {
+            if let Some(child) = self.root.children.pop() {
+                self.root = child;
+            }
+        }
+
+        self.len -= 1;
+        Some(removed)
+    }
+
+    pub fn range<R: RangeBounds<K>>(&self, range: R) -> Range<'_, K, V> {
+        let mut iter = Range {
+            stack: Vec::new(),
+            end: None,
+        };
+
+        match range.start_bound() {
+            Bound::Included(start) => {
+                iter.descend(&self.root, |n| n.keys.partition_point(|k| k < start))
+            }
+            Bound::Excluded(start) => {
+                iter.descend(&self.root, |n| n.keys.partition_point(|k| k <= start))
+            }
+            Bound::Unbounded => iter.descend(&self.root, |_| 0),
+        }
+
+        let mut end = Range {
+            stack: Vec::new(),
+            end: None,
+        };
+
+        match range.end_bound() {
+            Bound::Included(last) => {
+                end.descend(&self.root, |n| n.keys.partition_point(|k| k <= last))
+            }
+            Bound::Excluded(last) => {
+                end.descend(&self.root, |n| n.keys.partition_point(|k| k < last))
+            }
+            Bound::Unbounded => {}
+        }
+
+        iter.end = end.peek_key();
+
+        if let Some(first) = iter.peek_key() {
+            let past_end = match range.end_bound() {
+                Bound::Included(last) => first > last,
+                Bound::Excluded(last) => first >= last,
+                Bound::Unbounded => false,
+            };
+
+            if past_end {
+                iter.stack.clear();
+            }
+        }
+
+        iter
+    }
+
+    // Returns the displaced value, and the median and new right sibling if
+    // `node` had to split.
+    fn insert_rec(node: &mut Node<K, V>, key: K, value: V) -> (Option<V>, Split<K, V>) {
+        let i = match node.keys.binary_search(&key) {
+            Ok(i) => return (Some(std::mem::replace(&mut node.vals[i], value)), None),
+            Err(i) => i,
+        };
+
+        if node.is_leaf() {
+            node.keys.insert(i, key);
+            node.vals.insert(i, value);
+        } else {
+            let (old, split) = Self::insert_rec(&mut node.children[i], key, value);
+
+            match split {
+                Some((key, value, right)) => {
+                    node.keys.insert(i, key);
+                    node.vals.insert(i, value);
+                    node.children.insert(i + 1, right);
+                }
+                None => return (old, None),
+            }
+        }
+
+        if node.keys.len() < B {
+            return (None, None);
+        }
+
+        let mid = B / 2;
+        let mut right = Node::new(B);
+        right.keys.extend(node.keys.drain(mid + 1..));
+        right.vals.extend(node.vals.drain(mid + 1..));
+
+        if !node.is_leaf() {
+            right.children.extend(node.children.drain(mid + 1..));
+        }
+
+        let key = node.keys.pop().unwrap();
+        let value = node.vals.pop().unwrap();
+
+        (None, Some((key, value, right)))
+    }
+
+    fn remove_rec(node: &mut Node<K, V>, key: &K) -> Option<V> {
+        match node.keys.binary_search(key) {
+            Ok(i) if node.is_leaf() => {
+                node.keys.remove(i);
+                Some(node.vals.remove(i))
+            }
+            Ok(i) => {
+                let (key, value) = Self::remove_max(&mut node.children[i]);
+                node.keys[i] = key;
+                let removed = std::mem::replace(&mut node.vals[i], value);
+                Self::fix_child(node, i);
+                Some(removed)
+            }
+            Err(_) if node.is_leaf() => None,
+            Err(i) => {
+                let removed = Self::remove_rec(&mut node.children[i], key)?;
+                Self::fix_child(node, i);
+                Some(removed)
+            }
+        }
+    }
+}
+
+impl<K, V, const B: usize> Default for BTree<K, V, B> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Sorts by key and keeps the last value given for each key.
+impl<K: Ord, V, const B: usize> FromIterator<(K, V)> for BTree<K, V, B> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut items: Vec<(K, V)> = iter.into_iter().collect();
+        items.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let mut deduped: Vec<(K, V)> = Vec::with_capacity(items.len());
+
+        for item in items {
+            match deduped.last_mut() {
+                Some(last) if last.0 == item.0 => *last = item,
+                _ => deduped.push(item),
+            }
+        }
+
+        Self::from_sorted(deduped)
+    }
+}
+
+// Sizes of `parts` runs covering `total` items, differing by at most one.
+fn even_split(total: usize, parts: usize) -> impl Iterator<Item = usize> {
+    let base = total / parts;
+    let extra = total % parts;
+    (0..parts).map(move |j| base + (j < extra) as usize)
+}
+
+// Each stack entry is a node together with the index of the next key to
+// yield from it; everything in `children[index]` has already been yielded
+// or skipped.
+pub struct Range<'a, K, V> {
+    stack: Vec<(&'a Node<K, V>, usize)>,
+    end: Option<&'a K>,
+}
+
+impl<'a, K, V> Range<'a, K, V> {
+    fn descend(
+        &mut self,
+        mut node: &'a Node<K, V>,
+        mut position: impl FnMut(&Node<K, V>) -> usize,
+    ) {
+        loop {
+            let i = position(node);
+            self.stack.push((node, i));
+
+            match node.children.get(i) {
+                Some(child) => node = child,
+                None => break,
+            }
+        }
+
+        self.skip_exhausted();
+    }
+
+    fn skip_exhausted(&mut self) {
+        while let Some(&(node, i)) = self.stack.last() {
+            if i < node.keys.len() {
+                break;
+            }
+
+            self.stack.pop();
+        }
+    }
+
+    fn peek_key(&self) -> Option<&'a K> {
+        self.stack.last().map(|&(node, i)| &node.keys[i])
+    }
+}
+
+impl<'a, K, V> Iterator for Range<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (node, i) = self.stack.pop()?;
+        let key = &node.keys[i];
+
+        if self.end.is_some_and(|end| std::ptr::eq(end, key)) {
+            self.stack.clear();
+            return None;
+        }
+
+        self.stack.push((node, i + 1));
+
+        match node.children.get(i + 1) {
+            Some(child) => self.descend(child, |_| 0),
+            None => self.skip_exhausted(),
+        }
+
+        Some((key, &node.vals[i]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Checks key order, occupancy and that all leaves sit at the same depth,
+    // returning the depth of the leaves.
+    fn check<K: Ord, V, const B: usize>(tree: &BTree<K, V, B>) -> usize {
+        fn walk<K: Ord, V>(node: &Node<K, V>, b: usize, min: usize, root: bool) -> usize {
+            assert_eq!(node.keys.len(), node.vals.len());
+            assert!(node.keys.len() < b, "overfull node");
+            assert!(root || node.keys.len() >= min, "underfull node");
+            assert!(node.keys.windows(2).all(|w| w[0] < w[1]));
+
+            if node.is_leaf() {
+                return 1;
+            }
+
+            assert_eq!(node.children.len(), node.keys.len() + 1);
+
+            for (i, child) in node.children.iter().enumerate() {
+                if i > 0 {
+                    assert!(child.keys.iter().all(|k| *k > node.keys[i - 1]));
+                }
+
+                if i < node.keys.len() {
+                    assert!(child.keys.iter().all(|k| *k < node.keys[i]));
+                }
+            }
+
+            let depths: Vec<_> = node
+                .children
+                .iter()
+                .map(|child| walk(child, b, min, false))
+                .collect();
+            assert!(depths.windows(2).all(|w| w[0] == w[1]));
+
+            depths[0] + 1
+        }
+
+        let depth = walk(&tree.root, B, BTree::<K, V, B>::MIN_KEYS, true);
+        assert_eq!(tree.iter().count(), tree.len());
+        depth
+    }
+
+    fn keys<'a>(iter: Range<'a, u32, u32>) -> Vec<u32> {
+        iter.map(|(k, _)| *k).collect()
+    }
+
+    #[test]
+    fn insert_get() {
+        let mut tree: BTree<u32, u32, 3> = BTree::new();
+
+        for i in 0..100 {
+            assert_eq!(tree.insert((i * 37) % 100, i), None);
+            check(&tree);
+        }
+
+        assert_eq!(tree.len(), 100);
+        assert_eq!(tree.get(&37), Some(&1));
+        assert_eq!(tree.get(&100), None);
+        assert!(tree.contains_key(&0));
+
+        assert_eq!(tree.insert(37, 0), Some(1));
+        *tree.get_mut(&37).unwrap() += 5;
+        assert_eq!(tree.get(&37), Some(&5));
+        assert_eq!(tree.len(), 100);
+
+        assert_eq!(keys(tree.iter()), (0..100).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn remove() {
+        fn run<const B: usize>() {
+            let mut tree: BTree<u32, u32, B> = BTree::new();
+
+            for i in 0..500 {
+                tree.insert((i * 7919) % 500, i);
+            }
+
+            for i in 0..500 {
+                let key = (i * 6007) % 500;
+
+                if key % 3 != 0 {
+                    assert!(tree.remove(&key).is_some());
+                    assert_eq!(tree.remove(&key), None);
+                    check(&tree);
+                }
+            }
+
+            assert_eq!(keys(tree.iter()), (0..500).step_by(3).collect::<Vec<_>>());
+
+            for key in (0..500).step_by(3) {
+                assert!(tree.remove(&key).is_some());
+            }
+
+            assert!(tree.is_empty());
+            assert_eq!(tree.height(), 0);
+            assert_eq!(tree.iter().next(), None);
+        }
+
+        run::<3>();
+        run::<4>();
+        run::<5>();
+        run::<16>();
+    }
+
+    #[test]
+    fn range() {
+        let tree: BTree<u32, u32, 4> = (0..200).map(|k| (k * 2, k)).collect();
+
+        assert_eq!(keys(tree.range(10..20)), [10, 12, 14, 16, 18]);
+        assert_eq!(keys(tree.range(11..=20)), [12, 14, 16, 18, 20]);
+        assert_eq!(keys(tree.range(..5)), [0, 2, 4]);
+        assert_eq!(keys(tree.range(393..)), [394, 396, 398]);
+        assert_eq!(keys(tree.range(399..)), []);
+        assert_eq!(keys(tree.range(20..20)), []);
+        assert_eq!(
+            keys(tree.range((Bound::Excluded(20), Bound::Excluded(10)))),
+            []
+        );
+        assert_eq!(
+            keys(tree.range((Bound::Excluded(20), Bound::Included(26)))),
+            [22, 24, 26]
+        );
+        assert_eq!(tree.range(..).count(), 200);
+
+        for start in 0..50 {
+            for end in start..50 {
+                let expected: Vec<_> = (start..end).filter(|k| k % 2 == 0).collect();
+                assert_eq!(keys(tree.range(start..end)), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn bulk_load() {
+        fn run<const B: usize>(n: u32) {
+            let tree: BTree<u32, u32, B> = BTree::from_sorted((0..n).map(|k| (k, k * 10)));
+            check(&tree);
+
+            assert_eq!(tree.len(), n as usize);
+            assert_eq!(keys(tree.iter()), (0..n).collect::<Vec<_>>());
+
+            if n > 0 {
+                assert_eq!(tree.get(&(n - 1)), Some(&((n - 1) * 10)));
+            }
+        }
+
+        for n in 0..200 {
+            run::<3>(n);
+            run::<4>(n);
+            run::<7>(n);
+        }
+
+        run::<64>(100_000);
+
+        let mut tree: BTree<u32, u32, 5> = BTree::from_sorted((0..1000).map(|k| (k, k)));
+
+        for k in 0..1000 {
+            tree.remove(&k);
+            tree.insert(k + 1000, k);
+        }
+
+        check(&tree);
+    }
+
+    #[test]
+    fn from_unsorted() {
+        let tree: BTree<u32, &str, 3> = [(3, "c"), (1, "a"), (2, "b"), (1, "A")]
+            .into_iter()
+            .collect();
+
+        assert_eq!(tree.len(), 3);
+        assert_eq!(tree.get(&1), Some(&"A"));
+    }
+
+    #[test]
+    #[should_panic(expected = "strictly increasing")]
+    fn bulk_load_unsorted() {
+        BTree::<u32, (), 3>::from_sorted([(2, ()), (1, ())]);
+    }
+}
@@ -0,0 +1,521 @@
+use std::cmp::Ordering;
+use std::ops::Range;
+
+type Link<T, V> = Option<Box<Node<T, V>>>;
+
+// An AVL tree keyed on (start, end, id), where each node also records the
+// largest end point in its subtree. Ids are handed out in insertion order,
+// so identical intervals can coexist and sort by when they were added.
+struct Node<T, V> {
+    range: Range<T>,
+    id: usize,
+    value: V,
+    // A copy of the end point of some interval in this subtree. Hence
+    // `T: Clone`: a node cannot borrow from its own descendants, and they
+    // move around under rotations anyway.
+    max: T,
+    height: usize,
+    left: Link<T, V>,
+    right: Link<T, V>,
+}
+
+pub struct IntervalTree<T, V> {
+    root: Link<T, V>,
+    len: usize,
+    next_id: usize,
+}
+
+impl<T, V> IntervalTree<T, V> {
+    pub fn new() -> Self {
+        Self {
+            root: None,
+            len: 0,
+            next_id: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn height(&self) -> usize {
+        height(&self.root)
+    }
+}
+
+impl<T: Ord + Clone, V> IntervalTree<T, V> {
+    // Adds the interval even if an identical one is already present, and
+    // returns the id that tells them apart.
+    pub fn insert(&mut self, range: Range<T>, value: V) -> usize {
+        assert!(range.start < range.end, "empty interval");
+
+        let id = self.next_id;
+        self.next_id += 1;
+
+        self.root = Some(insert(self.root.take(), range, id, value));
+        self.len += 1;
+        id
+    }
+
+    pub fn remove(&mut self, range: &Range<T>, id: usize) -> Option<V> {
+        let (root, removed) = remove(self.root.take(), range, id);
+        self.root = root;
+
+        if removed.is_some() {
+            self.len -= 1;
+        }
+
+        removed
+    }
+
+    pub fn get(&self, range: &Range<T>, id: usize) -> Option<&V> {
+        let mut node = self.root.as_deref()?;
+
+        loop {
+            match cmp_key(range, id, node) {
+                Ordering::Less => node = node.left.as_deref()?,
+                Ordering::Greater => node = node.right.as_deref()?,
+                Ordering::Equal => return Some(&node.value),
+            }
+        }
+    }
+
+    // Intervals sharing at least one point with `range`, with their ids, by
+    // start point.
+    pub fn overlapping(&self, range: &Range<T>) -> Overlapping<'_, T, V> {
+        let root = if range.start < range.end {
+            self.root.as_deref()
+        } else {
+            None
+        };
+
+        Overlapping::new(root, range.start.clone(), range.end.clone(), false)
+    }
+
+    // Intervals containing `point`, by start point.
+    pub fn stabbing(&self, point: &T) -> Overlapping<'_, T, V> {
+        Overlapping::new(self.root.as_deref(), point.clone(), point.clone(), true)
+    }
+
+    pub fn iter(&self) -> Overlapping<'_, T, V> {
+        Overlapping::new(self.root.as_deref(), None, None, false)
+    }
+}
+
+impl<T, V> Default for IntervalTree<T, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn cmp_range<T: Ord>(a: &Range<T>, b: &Range<T>) -> Ordering {
+    a.start.cmp(&b.start).then_with(|| a.end.cmp(&b.end))
+}
+
+fn cmp_key<T: Ord, V>(range: &Range<T>, id: usize, node: &Node<T, V>) -> Ordering {
+    cmp_range(range, &node.range).then(id.cmp(&node.id))
+}
+
+fn height<T, V>(link: &Link<T, V>) -> usize {
+    link.as_ref().map_or(0, |node| node.height)
+}
+
+fn update<T: Ord + Clone, V>(node: &mut Node<T, V>) {
+    node.height = 1 + height(&node.left).max(height(&node.right));
+
+    let mut max = &node.range.end;
+
+    for child in [&node.left, &node.right].into_iter().flatten() {
+        if child.max > *max {
+            max = &child.max;
+        }
+    }
+
+    node.max = max.clone();
+}
+
+fn rotate_left<T: Ord + Clone, V>(mut x: Box<Node<T, V>>) -> Box<Node<T, V>> {
+    let mut y = x.right.take().expect("rotate_left without right child");
+    x.right = y.left.take();
+    update(&mut x);
+    y.left = Some(x);
+    update(&mut y);
+    y
+}
+
+fn rotate_right<T: Ord + Clone, V>(mut x: Box<Node<T, V>>) -> Box<Node<T, V>> {
+    let mut y = x.left.take().expect("rotate_right without left child");
+    x.left = y.right.take();
+    update(&mut x);
+    y.right = Some(x);
+    update(&mut y);
+    y
+}
+
+fn rebalance<T: Ord + Clone, V>(mut node: Box<Node<T, V>>) -> Box<Node<T, V>> {
+    update(&mut node);
+
+    let lh = height(&node.left);
+    let rh = height(&node.right);
+
+    if lh > rh + 1 {
+        let left = node.left.take().unwrap();
+
+        node.left = Some(if height(&left.left) < height(&left.right) {
+            rotate_left(left)
+        } else {
+            left
+        });
+
+        rotate_right(node)
+    } else if rh > lh + 1 {
+        let right = node.right.take().unwrap();
+
+        node.right = Some(if height(&right.right) < height(&right.left) {
+            rotate_right(right)
+        } else {
+            right
+        });
+
+        rotate_left(node)
+    } else {
+        node
+    }
+}
+
+fn insert<T: Ord + Clone, V>(
+    link: Link<T, V>,
+    range: Range<T>,
+    id: usize,
+    value: V,
+) -> Box<Node<T, V>> {
+    let Some(mut node) = link else {
+        return Box::new(Node {
+            max: range.end.clone(),
+            range,
+            id,
+            value,
+            height: 1,
+            left: None,
+            right: None,
+        });
+    };
+
+    // Ids are never reused, so the key cannot already be present.
+    if cmp_key(&range, id, &node) == Ordering::Less {
+        node.left = Some(insert(node.left.take(), range, id, value));
+    } else {
+        node.right = Some(insert(node.right.take(), range, id, value));
+    }
+
+    rebalance(node)
+}
+
+fn remove<T: Ord + Clone, V>(
+    link: Link<T, V>,
+    range: &Range<T>,
+    id: usize,
+) -> (Link<T, V>, Option<V>) {
+    let Some(mut node) = link else {
+        return (None, None);
+    };
+
+    let removed = match cmp_key(range, id, &node) {
+        Ordering::Less => {
+            let (left, removed) = remove(node.left.take(), range, id);
+            node.left = left;
+            removed
+        }
+        Ordering::Greater => {
+            let (right, removed) = remove(node.right.take(), range, id);
+            node.right = right;
+            removed
+        }
+        Ordering::Equal => {
+            let Node {
+                value, left, right, ..
+            } = *node;
+
+            let root = match (left, right) {
+                (None, child) | (child, None) => child,
+                (Some(left), Some(right)) => {
+                    let (rest, mut succ) = remove_min(right);
+                    succ.left = Some(left);
+                    succ.right = rest;
+                    Some(rebalance(succ))
+                }
+            };
+
+            return (root, Some(value));
+        }
+    };
+
+    (Some(rebalance(node)), removed)
+}
+
+fn remove_min<T: Ord + Clone, V>(mut node: Box<Node<T, V>>) -> (Link<T, V>, Box<Node<T, V>>) {
+    match node.left.take() {
+        None => (node.right.take(), node),
+        Some(left) => {
+            let (rest, min) = remove_min(left);
+            node.left = rest;
+            (Some(rebalance(node)), min)
+        }
+    }
+}
+
+// In-order walk that skips every subtree whose `max` ends at or before
+// `low`, and stops at the first node starting past `high`, since every
+// later node starts at least as late. Missing bounds match everything.
+pub struct Overlapping<'a, T, V> {
+    stack: Vec<&'a Node<T, V>>,
+    low: Option<T>,
+    high: Option<T>,
+    inclusive: bool,
+}
+
+impl<'a, T: Ord, V> Overlapping<'a, T, V> {
+    fn new(
+        root: Option<&'a Node<T, V>>,
+        low: impl Into<Option<T>>,
+        high: impl Into<Option<T>>,
+        inclusive: bool,
+    ) -> Self {
+        let mut iter = Self {
+            stack: Vec::new(),
+            low: low.into(),
+            high: high.into(),
+            inclusive,
+        };
+
+        iter.push_left(root);
+        iter
+    }
+
+    fn ends_after_low(&self, end: &T) -> bool {
+        self.low.as_ref().is_none_or(|low| end > low)
+    }
+
+    fn starts_before_high(&self, start: &T) -> bool {
+        match &self.high {
+            Some(high) if self.inclusive => start <= high,
+            Some(high) => start < high,
+            None => true,
+        }
+    }
+
+    fn push_left(&mut self, mut node: Option<&'a Node<T, V>>) {
+        while let Some(curr) = node {
+            if !self.ends_after_low(&curr.max) {
+                break;
+            }
+
+            self.stack.push(curr);
+            node = curr.left.as_deref();
+        }
+    }
+}
+
+impl<'a, T: Ord, V> Iterator for Overlapping<'a, T, V> {
+    type Item = (usize, &'a Range<T>, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(node) = self.stack.pop() {
+            if !self.starts_before_high(&node.range.start) {
+                self.stack.clear();
+                return None;
+            }
+
+            self.push_left(node.right.as_deref());
+
+            if self.ends_after_low(&node.range.end) {
+                return Some((node.id, &node.range, &node.value));
+            }
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check<T: Ord + Clone + std::fmt::Debug, V>(link: &Link<T, V>) -> usize {
+        let Some(node) = link else {
+            return 0;
+        };
+
+        let lh = check(&node.left);
+        let rh = check(&node.right);
+        assert!(lh.abs_diff(rh) <= 1);
+
+        let mut max = node.range.end.clone();
+
+        for child in [&node.left, &node.right].into_iter().flatten() {
+            max = max.max(child.max.clone());
+        }
+
+        assert_eq!(node.max, max);
+        1 + lh.max(rh)
+    }
+
+    // CLRS figure 14.4, made half-open.
+    fn new_tree() -> IntervalTree<u32, &'static str> {
+        let mut tree = IntervalTree::new();
+
+        for (i, (range, name)) in [
+            (16..22, "a"),
+            (8..10, "b"),
+            (25..31, "c"),
+            (5..9, "d"),
+            (15..24, "e"),
+            (17..20, "f"),
+            (26..27, "g"),
+            (0..4, "h"),
+            (6..11, "i"),
+            (19..21, "j"),
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            assert_eq!(tree.insert(range, name), i);
+        }
+
+        tree
+    }
+
+    fn names<'a>(iter: Overlapping<'a, u32, &'static str>) -> Vec<&'static str> {
+        iter.map(|(_, _, name)| *name).collect()
+    }
+
+    #[test]
+    fn insert_get() {
+        let mut tree = new_tree();
+        check(&tree.root);
+
+        assert_eq!(tree.len(), 10);
+        assert_eq!(tree.get(&(15..24), 4), Some(&"e"));
+        assert_eq!(tree.get(&(15..24), 3), None);
+        assert_eq!(tree.get(&(15..23), 4), None);
+
+        assert_eq!(tree.insert(15..24, "E"), 10);
+        assert_eq!(tree.len(), 11);
+        assert_eq!(tree.get(&(15..24), 4), Some(&"e"));
+        assert_eq!(tree.get(&(15..24), 10), Some(&"E"));
+        check(&tree.root);
+
+        let starts: Vec<_> = tree.iter().map(|(_, r, _)| r.start).collect();
+        assert_eq!(starts, [0, 5, 6, 8, 15, 15, 16, 17, 19, 25, 26]);
+    }
+
+    #[test]
+    fn identical_reservations() {
+        let mut tree = IntervalTree::new();
+        let first = tree.insert(9..12, "room booked by ana");
+        let second = tree.insert(9..12, "room booked by bo");
+        tree.insert(12..13, "after");
+
+        let found: Vec<_> = tree.overlapping(&(10..11)).collect();
+        assert_eq!(
+            found,
+            [
+                (first, &(9..12), &"room booked by ana"),
+                (second, &(9..12), &"room booked by bo"),
+            ]
+        );
+
+        // Cancelling one booking leaves the other in place.
+        assert_eq!(tree.remove(&(9..12), second), Some("room booked by bo"));
+        assert_eq!(tree.remove(&(9..12), second), None);
+        assert_eq!(names(tree.stabbing(&9)), ["room booked by ana"]);
+        assert_eq!(tree.len(), 2);
+        check(&tree.root);
+    }
+
+    #[test]
+    fn overlapping() {
+        let tree = new_tree();
+
+        assert_eq!(names(tree.overlapping(&(22..25))), ["e"]);
+        assert_eq!(names(tree.overlapping(&(9..16))), ["i", "b", "e"]);
+        assert_eq!(names(tree.overlapping(&(11..15))), Vec::<&str>::new());
+        assert_eq!(names(tree.overlapping(&(4..5))), Vec::<&str>::new());
+        assert_eq!(names(tree.overlapping(&(30..100))), ["c"]);
+        assert_eq!(names(tree.overlapping(&(10..10))), Vec::<&str>::new());
+        assert_eq!(tree.overlapping(&(0..100)).count(), 10);
+    }
+
+    #[test]
+    fn stabbing() {
+        let tree = new_tree();
+
+        assert_eq!(names(tree.stabbing(&19)), ["e", "a", "f", "j"]);
+        assert_eq!(names(tree.stabbing(&8)), ["d", "i", "b"]);
+        assert_eq!(names(tree.stabbing(&4)), Vec::<&str>::new());
+        assert_eq!(names(tree.stabbing(&0)), ["h"]);
+        assert_eq!(names(tree.stabbing(&31)), Vec::<&str>::new());
+    }
+
+    #[test]
+    fn remove() {
+        let mut tree = new_tree();
+
+        assert_eq!(tree.remove(&(15..24), 4), Some("e"));
+        assert_eq!(tree.remove(&(15..24), 4), None);
+        assert_eq!(tree.remove(&(16..21), 0), None);
+        assert_eq!(tree.remove(&(16..22), 1), None);
+        assert_eq!(tree.remove(&(16..22), 0), Some("a"));
+        check(&tree.root);
+
+        assert_eq!(tree.len(), 8);
+        assert_eq!(names(tree.stabbing(&19)), ["f", "j"]);
+        assert_eq!(names(tree.overlapping(&(22..25))), Vec::<&str>::new());
+    }
+
+    #[test]
+    fn matches_brute_force() {
+        let mut tree = IntervalTree::new();
+        let mut all = Vec::new();
+
+        for i in 0..300u32 {
+            let start = (i * 7919) % 1000;
+            let end = start + 1 + (i * 31) % 50;
+            let id = tree.insert(start..end, i);
+            all.push((start..end, id));
+        }
+
+        for (range, id) in all.iter().step_by(2) {
+            assert!(tree.remove(range, *id).is_some());
+        }
+
+        check(&tree.root);
+        assert!(tree.height() <= 12);
+
+        let mut kept: Vec<_> = all
+            .iter()
+            .skip(1)
+            .step_by(2)
+            .map(|(r, _)| r.clone())
+            .collect();
+        kept.sort_by(cmp_range);
+
+        for q in (0..1100).step_by(37) {
+            let query = q..q + 20;
+            let expected: Vec<_> = kept
+                .iter()
+                .filter(|r| r.start < query.end && query.start < r.end)
+                .collect();
+            let found: Vec<_> = tree.overlapping(&query).map(|(_, r, _)| r).collect();
+            assert_eq!(found, expected);
+
+            let expected: Vec<_> = kept.iter().filter(|r| r.contains(&q)).collect();
+            let found: Vec<_> = tree.stabbing(&q).map(|(_, r, _)| r).collect();
+            assert_eq!(found, expected);
+        }
+    }
+}
@@ -1 +1,9 @@
+pub mod avl;
 pub mod binary_tree;
+pub mod btree;
+pub mod interval;
+pub mod persistent;
+pub mod rbtree;
+pub mod splay;
+pub mod treap;
+pub mod trie;
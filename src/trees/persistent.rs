@@ -0,0 +1,408 @@
+use std::cmp::Ordering;
+use std::rc::Rc;
+
+type Link<K, V> = Option<Rc<Node<K, V>>>;
+
+// An AVL tree whose nodes are never mutated once shared. Updates copy the
+// path from the root to the change and reuse every subtree off that path.
+// Values sit behind their own `Rc`, so a copied node shares its value too.
+struct Node<K, V> {
+    key: K,
+    value: Rc<V>,
+    height: usize,
+    left: Link<K, V>,
+    right: Link<K, V>,
+}
+
+pub struct PMap<K, V> {
+    root: Link<K, V>,
+    len: usize,
+}
+
+impl<K, V> PMap<K, V> {
+    pub fn new() -> Self {
+        Self { root: None, len: 0 }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn height(&self) -> usize {
+        height(&self.root)
+    }
+
+    pub fn first(&self) -> Option<(&K, &V)> {
+        let mut node = self.root.as_deref()?;
+
+        while let Some(left) = node.left.as_deref() {
+            node = left;
+        }
+
+        Some((&node.key, &node.value))
+    }
+
+    pub fn last(&self) -> Option<(&K, &V)> {
+        let mut node = self.root.as_deref()?;
+
+        while let Some(right) = node.right.as_deref() {
+            node = right;
+        }
+
+        Some((&node.key, &node.value))
+    }
+
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        let mut iter = Iter { stack: Vec::new() };
+        iter.push_left(self.root.as_deref());
+        iter
+    }
+}
+
+impl<K: Ord, V> PMap<K, V> {
+    pub fn get(&self, key: &K) -> Option<&V> {
+        let mut node = self.root.as_deref()?;
+
+        loop {
+            match key.cmp(&node.key) {
+                Ordering::Less => node = node.left.as_deref()?,
+                Ordering::Greater => node = node.right.as_deref()?,
+                Ordering::Equal => return Some(&node.value),
+            }
+        }
+    }
+
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.get(key).is_some()
+    }
+}
+
+impl<K: Ord + Clone, V> PMap<K, V> {
+    pub fn insert(&self, key: K, value: V) -> Self {
+        let (root, replaced) = insert(&self.root, key, Rc::new(value));
+
+        Self {
+            root: Some(root),
+            len: if replaced { self.len } else { self.len + 1 },
+        }
+    }
+
+    // Returns a copy of this map if `key` is not present.
+    pub fn remove(&self, key: &K) -> Self {
+        match remove(&self.root, key) {
+            Some(root) => Self {
+                root,
+                len: self.len - 1,
+            },
+            None => self.clone(),
+        }
+    }
+}
+
+impl<K, V> Clone for PMap<K, V> {
+    fn clone(&self) -> Self {
+        Self {
+            root: self.root.clone(),
+            len: self.len,
+        }
+    }
+}
+
+impl<K, V> Default for PMap<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Frees the nodes this map owns outright and stops at any subtree still
+// shared with another version.
+impl<K, V> Drop for PMap<K, V> {
+    fn drop(&mut self) {
+        let mut stack = Vec::from_iter(self.root.take());
+
+        while let Some(rc) = stack.pop() {
+            if let Ok(mut node) = Rc::try_unwrap(rc) {
+                stack.extend(node.left.take());
+                stack.extend(node.right.take());
+            }
+        }
+    }
+}
+
+fn height<K, V>(link: &Link<K, V>) -> usize {
+    link.as_ref().map_or(0, |node| node.height)
+}
+
+fn node<K, V>(key: K, value: Rc<V>, left: Link<K, V>, right: Link<K, V>) -> Rc<Node<K, V>> {
+    Rc::new(Node {
+        key,
+        value,
+        height: 1 + height(&left).max(height(&right)),
+        left,
+        right,
+    })
+}
+
+// Builds a node from children whose heights differ by at most two, rotating
+// copies of the taller side as needed.
+fn balance<K: Clone, V>(
+    key: K,
+    value: Rc<V>,
+    left: Link<K, V>,
+    right: Link<K, V>,
+) -> Rc<Node<K, V>> {
+    let (hl, hr) = (height(&left), height(&right));
+
+    if hl > hr + 1 {
+        let l = left.unwrap();
+
+        if height(&l.left) >= height(&l.right) {
+            let right = node(key, value, l.right.clone(), right);
+            node(l.key.clone(), l.value.clone(), l.left.clone(), Some(right))
+        } else {
+            let lr = l.right.as_deref().unwrap();
+            let left = node(
+                l.key.clone(),
+                l.value.clone(),
+                l.left.clone(),
+                lr.left.clone(),
+            );
+            let right = node(key, value, lr.right.clone(), right);
+            node(lr.key.clone(), lr.value.clone(), Some(left), Some(right))
+        }
+    } else if hr > hl + 1 {
+        let r = right.unwrap();
+
+        if height(&r.right) >= height(&r.left) {
+            let left = node(key, value, left, r.left.clone());
+            node(r.key.clone(), r.value.clone(), Some(left), r.right.clone())
+        } else {
+            let rl = r.left.as_deref().unwrap();
+            let left = node(key, value, left, rl.left.clone());
+            let right = node(
+                r.key.clone(),
+                r.value.clone(),
+                rl.right.clone(),
+                r.right.clone(),
+            );
+            node(rl.key.clone(), rl.value.clone(), Some(left), Some(right))
+        }
+    } else {
+        node(key, value, left, right)
+    }
+}
+
+fn insert<K: Ord + Clone, V>(link: &Link<K, V>, key: K, value: Rc<V>) -> (Rc<Node<K, V>>, bool) {
+    let Some(n) = link else {
+        return (node(key, value, None, None), false);
+    };
+
+    match key.cmp(&n.key) {
+        Ordering::Less => {
+            let (left, replaced) = insert(&n.left, key, value);
+            let root = balance(n.key.clone(), n.value.clone(), Some(left), n.right.clone());
+            (root, replaced)
+        }
+        Ordering::Greater => {
+            let (right, replaced) = insert(&n.right, key, value);
+            let root = balance(n.key.clone(), n.value.clone(), n.left.clone(), Some(right));
+            (root, replaced)
+        }
+        Ordering::Equal => (node(key, value, n.left.clone(), n.right.clone()), true),
+    }
+}
+
+// Returns `None` if `key` is not in the tree.
+fn remove<K: Ord + Clone, V>(link: &Link<K, V>, key: &K) -> Option<Link<K, V>> {
+    let n = link.as_ref()?;
+
+    let root = match key.cmp(&n.key) {
+        Ordering::Less => {
+            let left = remove(&n.left, key)?;
+            balance(n.key.clone(), n.value.clone(), left, n.right.clone())
+        }
+        Ordering::Greater => {
+            let right = remove(&n.right, key)?;
+            balance(n.key.clone(), n.value.clone(), n.left.clone(), right)
+        }
+        Ordering::Equal => match (&n.left, &n.right) {
+            (None, right) => return Some(right.clone()),
+            (left, None) => return Some(left.clone()),
+            (left, Some(right)) => {
+                let (key, value, right) = remove_min(right);
+                balance(key, value, left.clone(), right)
+            }
+        },
+    };
+
+    Some(Some(root))
+}
+
+fn remove_min<K: Clone, V>(n: &Rc<Node<K, V>>) -> (K, Rc<V>, Link<K, V>) {
+    match &n.left {
+        None => (n.key.clone(), n.value.clone(), n.right.clone()),
+        Some(left) => {
+            let (key, value, left) = remove_min(left);
+            let root = balance(n.key.clone(), n.value.clone(), left, n.right.clone());
+            (key, value, Some(root))
+        }
+    }
+}
+
+pub struct Iter<'a, K, V> {
+    stack: Vec<&'a Node<K, V>>,
+}
+
+impl<'a, K, V> Iter<'a, K, V> {
+    fn push_left(&mut self, mut node: Option<&'a Node<K, V>>) {
+        while let Some(curr) = node {
+            self.stack.push(curr);
+            node = curr.left.as_deref();
+        }
+    }
+}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.stack.pop()?;
+        self.push_left(node.right.as_deref());
+        Some((&node.key, &node.value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check<K: Ord, V>(link: &Link<K, V>) -> usize {
+        let Some(node) = link else {
+            return 0;
+        };
+
+        if let Some(left) = &node.left {
+            assert!(left.key < node.key);
+        }
+
+        if let Some(right) = &node.right {
+            assert!(right.key > node.key);
+        }
+
+        let (hl, hr) = (check(&node.left), check(&node.right));
+        assert!(hl.abs_diff(hr) <= 1, "unbalanced");
+        assert_eq!(node.height, 1 + hl.max(hr));
+        node.height
+    }
+
+    fn keys(map: &PMap<u32, u32>) -> Vec<u32> {
+        map.iter().map(|(k, _)| *k).collect()
+    }
+
+    #[test]
+    fn insert_remove() {
+        let mut map = PMap::new();
+
+        for i in 0..1000u32 {
+            map = map.insert((i * 7919) % 1000, i);
+        }
+
+        check(&map.root);
+        assert_eq!(map.len(), 1000);
+        assert!(map.height() <= 15);
+        assert_eq!(map.get(&0), Some(&0));
+        assert_eq!(map.get(&1000), None);
+        assert_eq!(map.first().map(|(k, _)| *k), Some(0));
+        assert_eq!(map.last().map(|(k, _)| *k), Some(999));
+
+        for key in (0..1000).filter(|k| k % 5 != 0) {
+            map = map.remove(&key);
+            assert!(!map.contains_key(&key));
+        }
+
+        check(&map.root);
+        assert_eq!(map.len(), 200);
+        assert_eq!(map.remove(&1).len(), 200);
+        assert_eq!(keys(&map), (0..1000).step_by(5).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn versions_are_independent() {
+        let v0 = PMap::new();
+        let v1 = v0.insert(1, 10).insert(2, 20).insert(3, 30);
+        let v2 = v1.insert(2, 21);
+        let v3 = v2.remove(&1);
+
+        assert!(v0.is_empty());
+        assert_eq!(v1.get(&2), Some(&20));
+        assert_eq!(v2.get(&2), Some(&21));
+        assert_eq!(v2.len(), 3);
+        assert_eq!(keys(&v1), [1, 2, 3]);
+        assert_eq!(keys(&v3), [2, 3]);
+
+        drop(v1);
+        assert_eq!(v2.get(&1), Some(&10));
+    }
+
+    #[test]
+    fn untouched_subtrees_are_shared() {
+        let mut map = PMap::new();
+
+        for key in 0..127u32 {
+            map = map.insert(key, key);
+        }
+
+        let root = map.root.as_ref().unwrap();
+        let next = map.insert(0, 1);
+        let next_root = next.root.as_ref().unwrap();
+
+        assert!(!Rc::ptr_eq(root, next_root));
+        assert!(!Rc::ptr_eq(
+            root.left.as_ref().unwrap(),
+            next_root.left.as_ref().unwrap()
+        ));
+        assert!(Rc::ptr_eq(
+            root.right.as_ref().unwrap(),
+            next_root.right.as_ref().unwrap()
+        ));
+        assert_eq!(Rc::strong_count(root.right.as_ref().unwrap()), 2);
+    }
+
+    #[test]
+    fn values_are_shared_not_cloned() {
+        // Not `Clone`: updates may only ever copy pointers to it.
+        struct Blob(Vec<u8>);
+
+        let mut map = PMap::new();
+
+        for key in 0..100u32 {
+            map = map.insert(key, Blob(vec![key as u8; 1024]));
+        }
+
+        let next = map.insert(100, Blob(Vec::new())).remove(&0);
+        assert_eq!(next.get(&50).map(|b| b.0[0]), Some(50));
+        assert!(std::ptr::eq(map.get(&50).unwrap(), next.get(&50).unwrap()));
+
+        // The root is copied by every update, but its value is not.
+        let root = map.root.as_ref().unwrap();
+        assert!(std::ptr::eq(&*root.value, next.get(&root.key).unwrap()));
+        assert_eq!(Rc::strong_count(&root.value), 2);
+    }
+
+    #[test]
+    fn drop_history() {
+        let mut history = vec![PMap::new()];
+
+        for key in 0..20_000u32 {
+            let next = history.last().unwrap().insert(key, key);
+            history.push(next);
+        }
+
+        assert_eq!(history.last().unwrap().len(), 20_000);
+        drop(history);
+    }
+}
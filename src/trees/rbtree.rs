@@ -0,0 +1,712 @@
+use std::cmp::Ordering;
+use std::marker::PhantomData;
+use std::ptr::NonNull;
+
+type Link<K, V> = Option<NonNull<Node<K, V>>>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Color {
+    Red,
+    Black,
+}
+
+struct Node<K, V> {
+    key: K,
+    value: V,
+    color: Color,
+    parent: Link<K, V>,
+    left: Link<K, V>,
+    right: Link<K, V>,
+}
+
+pub struct RbTree<K, V> {
+    root: Link<K, V>,
+    len: usize,
+    phantom: PhantomData<Box<Node<K, V>>>,
+}
+
+impl<K, V> RbTree<K, V> {
+    pub fn new() -> Self {
+        Self {
+            root: None,
+            len: 0,
+            phantom: PhantomData,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn height(&self) -> usize {
+        let mut height = 0;
+        let mut stack = Vec::from_iter(self.root.map(|root| (root, 1)));
+
+        while let Some((node, depth)) = stack.pop() {
+            height = height.max(depth);
+
+            unsafe {
+                stack.extend(node.as_ref().left.map(|left| (left, depth + 1)));
+                stack.extend(node.as_ref().right.map(|right| (right, depth + 1)));
+            }
+        }
+
+        height
+    }
+
+    pub fn min(&self) -> Option<(&K, &V)> {
+        self.root.map(|root| unsafe { entry(minimum(root)) })
+    }
+
+    pub fn max(&self) -> Option<(&K, &V)> {
+        self.root.map(|root| unsafe { entry(maximum(root)) })
+    }
+
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        Iter::new(self.root, Order::In)
+    }
+
+    pub fn inorder(&self) -> Iter<'_, K, V> {
+        Iter::new(self.root, Order::In)
+    }
+
+    pub fn preorder(&self) -> Iter<'_, K, V> {
+        Iter::new(self.root, Order::Pre)
+    }
+
+    pub fn postorder(&self) -> Iter<'_, K, V> {
+        Iter::new(self.root, Order::Post)
+    }
+
+    fn rotate_left(&mut self, x: NonNull<Node<K, V>>) {
+        unsafe {
+            let x = x.as_ptr();
+            let y = (*x)
+                .right
+                .expect("rotate_left without right child")
+                .as_ptr();
+
+            (*x).right = (*y).left;
+            if let Some(mut left) = (*y).left {
+                left.as_mut().parent = NonNull::new(x);
+            }
+
+            self.replace_child((*x).parent, NonNull::new(x), NonNull::new(y));
+            (*y).parent = (*x).parent;
+
+            (*y).left = NonNull::new(x);
+            (*x).parent = NonNull::new(y);
+        }
+    }
+
+    fn rotate_right(&mut self, x: NonNull<Node<K, V>>) {
+        unsafe {
+            let x = x.as_ptr();
+            let y = (*x).left.expect("rotate_right without left child").as_ptr();
+
+            (*x).left = (*y).right;
+            if let Some(mut right) = (*y).right {
+                right.as_mut().parent = NonNull::new(x);
+            }
+
+            self.replace_child((*x).parent, NonNull::new(x), NonNull::new(y));
+            (*y).parent = (*x).parent;
+
+            (*y).right = NonNull::new(x);
+            (*x).parent = NonNull::new(y);
+        }
+    }
+
+    // Points whichever link of `parent` held `old` at `new` instead.
+    fn replace_child(&mut self, parent: Link<K, V>, old: Link<K, V>, new: Link<K, V>) {
+        unsafe {
+            match parent {
+                None => self.root = new,
+                Some(mut parent) if parent.as_ref().left == old => parent.as_mut().left = new,
+                Some(mut parent) => parent.as_mut().right = new,
+            }
+        }
+    }
+
+    // CLRS RB-TRANSPLANT: replaces the subtree rooted at `u` with the one
+    // rooted at `v`.
+    fn transplant(&mut self, u: NonNull<Node<K, V>>, v: Link<K, V>) {
+        unsafe {
+            let parent = u.as_ref().parent;
+            self.replace_child(parent, Some(u), v);
+
+            if let Some(mut v) = v {
+                v.as_mut().parent = parent;
+            }
+        }
+    }
+
+    fn insert_fixup(&mut self, mut z: NonNull<Node<K, V>>) {
+        unsafe {
+            loop {
+                let mut p = match z.as_ref().parent {
+                    Some(p) if p.as_ref().color == Color::Red => p,
+                    _ => break,
+                };
+                let mut g = p.as_ref().parent.expect("red node without parent");
+
+                if g.as_ref().left == Some(p) {
+                    let uncle = g.as_ref().right;
+
+                    if is_red(uncle) {
+                        p.as_mut().color = Color::Black;
+                        uncle.unwrap().as_mut().color = Color::Black;
+                        g.as_mut().color = Color::Red;
+                        z = g;
+                    } else {
+                        if p.as_ref().right == Some(z) {
+                            z = p;
+                            self.rotate_left(z);
+                            p = z.as_ref().parent.unwrap();
+                        }
+
+                        p.as_mut().color = Color::Black;
+                        g.as_mut().color = Color::Red;
+                        self.rotate_right(g);
+                    }
+                } else {
+                    let uncle = g.as_ref().left;
+
+                    if is_red(uncle) {
+                        p.as_mut().color = Color::Black;
+                        uncle.unwrap().as_mut().color = Color::Black;
+                        g.as_mut().color = Color::Red;
+                        z = g;
+                    } else {
+                        if p.as_ref().left == Some(z) {
+                            z = p;
+                            self.rotate_right(z);
+                            p = z.as_ref().parent.unwrap();
+                        }
+
+                        p.as_mut().color = Color::Black;
+                        g.as_mut().color = Color::Red;
+                        self.rotate_left(g);
+                    }
+                }
+            }
+
+            if let Some(mut root) = self.root {
+                root.as_mut().color = Color::Black;
+            }
+        }
+    }
+
+    // `x` may be nil, so its parent is tracked separately.
+    fn delete_fixup(&mut self, mut x: Link<K, V>, mut parent: Link<K, V>) {
+        unsafe {
+            while x != self.root && !is_red(x) {
+                let mut p = parent.expect("non-root node without parent");
+
+                if p.as_ref().left == x {
+                    let mut w = p.as_ref().right.expect("missing sibling");
+
+                    if w.as_ref().color == Color::Red {
+                        w.as_mut().color = Color::Black;
+                        p.as_mut().color = Color::Red;
+                        self.rotate_left(p);
+                        w = p.as_ref().right.unwrap();
+                    }
+
+                    if !is_red(w.as_ref().left) && !is_red(w.as_ref().right) {
+                        w.as_mut().color = Color::Red;
+                        x = Some(p);
+                        parent = p.as_ref().parent;
+                    } else {
+                        if !is_red(w.as_ref().right) {
+                            w.as_ref().left.unwrap().as_mut().color = Color::Black;
+                            w.as_mut().color = Color::Red;
+                            self.rotate_right(w);
+                            w = p.as_ref().right.unwrap();
+                        }
+
+                        w.as_mut().color = p.as_ref().color;
+                        p.as_mut().color = Color::Black;
+                        w.as_ref().right.unwrap().as_mut().color = Color::Black;
+                        self.rotate_left(p);
+                        x = self.root;
+                        parent = None;
+                    }
+                } else {
+                    let mut w = p.as_ref().left.expect("missing sibling");
+
+                    if w.as_ref().color == Color::Red {
+                        w.as_mut().color = Color::Black;
+                        p.as_mut().color = Color::Red;
+                        self.rotate_right(p);
+                        w = p.as_ref().left.unwrap();
+                    }
+
+                    if !is_red(w.as_ref().left) && !is_red(w.as_ref().right) {
+                        w.as_mut().color = Color::Red;
+                        x = Some(p);
+                        parent = p.as_ref().parent;
+                    } else {
+                        if !is_red(w.as_ref().left) {
+                            w.as_ref().right.unwrap().as_mut().color = Color::Black;
+                            w.as_mut().color = Color::Red;
+                            self.rotate_left(w);
+                            w = p.as_ref().left.unwrap();
+                        }
+
+                        w.as_mut().color = p.as_ref().color;
+                        p.as_mut().color = Color::Black;
+                        w.as_ref().left.unwrap().as_mut().color = Color::Black;
+                        self.rotate_right(p);
+                        x = self.root;
+                        parent = None;
+                    }
+                }
+            }
+
+            if let Some(mut x) = x {
+                x.as_mut().color = Color::Black;
+            }
+        }
+    }
+}
+
+impl<K: Ord, V> RbTree<K, V> {
+    pub fn get(&self, key: &K) -> Option<&V> {
+        self.find(key)
+            .map(|node| unsafe { &(*node.as_ptr()).value })
+    }
+
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        self.find(key)
+            .map(|node| unsafe { &mut (*node.as_ptr()).value })
+    }
+
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.find(key).is_some()
+    }
+
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let mut parent = None;
+        let mut curr = self.root;
+
+        unsafe {
+            while let Some(node) = curr {
+                parent = curr;
+
+                match key.cmp(&(*node.as_ptr()).key) {
+                    Ordering::Less => curr = node.as_ref().left,
+                    Ordering::Greater => curr = node.as_ref().right,
+                    Ordering::Equal => {
+                        return Some(std::mem::replace(&mut (*node.as_ptr()).value, value));
+                    }
+                }
+            }
+
+            let z = NonNull::new_unchecked(Box::into_raw(Box::new(Node {
+                key,
+                value,
+                color: Color::Red,
+                parent,
+                left: None,
+                right: None,
+            })));
+
+            match parent {
+                None => self.root = Some(z),
+                Some(mut parent) => {
+                    if z.as_ref().key < parent.as_ref().key {
+                        parent.as_mut().left = Some(z);
+                    } else {
+                        parent.as_mut().right = Some(z);
+                    }
+                }
+            }
+
+            self.len += 1;
+            self.insert_fixup(z);
+        }
+
+        None
+    }
+
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let z = self.find(key)?;
+
+        unsafe {
+            let mut removed_color = z.as_ref().color;
+            let x;
+            let x_parent;
+
+            if z.as_ref().left.is_none() {
+                x = z.as_ref().right;
+                x_parent = z.as_ref().parent;
+                self.transplant(z, x);
+            } else if z.as_ref().right.is_none() {
+                x = z.as_ref().left;
+                x_parent = z.as_ref().parent;
+                self.transplant(z, x);
+            } else {
+                let mut y = minimum(z.as_ref().right.unwrap());
+                removed_color = y.as_ref().color;
+                x = y.as_ref().right;
+
+                if y.as_ref().parent == Some(z) {
+                    x_parent = Some(y);
+                } else {
+                    x_parent = y.as_ref().parent;
+                    self.transplant(y, x);
+                    y.as_mut().right = z.as_ref().right;
+                    y.as_ref().right.unwrap().as_mut().parent = Some(y);
+                }
+
+                self.transplant(z, Some(y));
+                y.as_mut().left = z.as_ref().left;
+                y.as_ref().left.unwrap().as_mut().parent = Some(y);
+                y.as_mut().color = z.as_ref().color;
+            }
+
+            if removed_color == Color::Black {
+                self.delete_fixup(x, x_parent);
+            }
+
+            self.len -= 1;
+            Some(Box::from_raw(z.as_ptr()).value)
+        }
+    }
+
+    // Largest entry with a key strictly less than `key`.
+    pub fn predecessor(&self, key: &K) -> Option<(&K, &V)> {
+        let mut candidate = None;
+        let mut curr = self.root;
+
+        unsafe {
+            while let Some(node) = curr {
+                if (*node.as_ptr()).key < *key {
+                    candidate = curr;
+                    curr = node.as_ref().right;
+                } else {
+                    curr = node.as_ref().left;
+                }
+            }
+
+            candidate.map(|node| entry(node))
+        }
+    }
+
+    // Smallest entry with a key strictly greater than `key`.
+    pub fn successor(&self, key: &K) -> Option<(&K, &V)> {
+        let mut candidate = None;
+        let mut curr = self.root;
+
+        unsafe {
+            while let Some(node) = curr {
+                if (*node.as_ptr()).key > *key {
+                    candidate = curr;
+                    curr = node.as_ref().left;
+                } else {
+                    curr = node.as_ref().right;
+                }
+            }
+
+            candidate.map(|node| entry(node))
+        }
+    }
+
+    fn find(&self, key: &K) -> Link<K, V> {
+        let mut curr = self.root;
+
+        unsafe {
+            while let Some(node) = curr {
+                match key.cmp(&(*node.as_ptr()).key) {
+                    Ordering::Less => curr = node.as_ref().left,
+                    Ordering::Greater => curr = node.as_ref().right,
+                    Ordering::Equal => return curr,
+                }
+            }
+        }
+
+        None
+    }
+}
+
+fn is_red<K, V>(link: Link<K, V>) -> bool {
+    link.is_some_and(|node| unsafe { node.as_ref().color == Color::Red })
+}
+
+unsafe fn minimum<K, V>(mut node: NonNull<Node<K, V>>) -> NonNull<Node<K, V>> {
+    while let Some(left) = node.as_ref().left {
+        node = left;
+    }
+
+    node
+}
+
+unsafe fn maximum<K, V>(mut node: NonNull<Node<K, V>>) -> NonNull<Node<K, V>> {
+    while let Some(right) = node.as_ref().right {
+        node = right;
+    }
+
+    node
+}
+
+unsafe fn entry<'a, K, V>(node: NonNull<Node<K, V>>) -> (&'a K, &'a V) {
+    (&(*node.as_ptr()).key, &(*node.as_ptr()).value)
+}
+
+impl<K, V> Default for RbTree<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, V> Drop for RbTree<K, V> {
+    fn drop(&mut self) {
+        let mut stack = Vec::from_iter(self.root.take());
+
+        while let Some(node) = stack.pop() {
+            unsafe {
+                let node = Box::from_raw(node.as_ptr());
+                stack.extend(node.left);
+                stack.extend(node.right);
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Order {
+    Pre,
+    In,
+    Post,
+}
+
+// Each stack entry carries whether the node's children have already been
+// pushed, so a single explicit stack serves all three orders.
+pub struct Iter<'a, K, V> {
+    stack: Vec<(NonNull<Node<K, V>>, bool)>,
+    order: Order,
+    phantom: PhantomData<&'a Node<K, V>>,
+}
+
+impl<'a, K, V> Iter<'a, K, V> {
+    fn new(root: Link<K, V>, order: Order) -> Self {
+        Self {
+            stack: Vec::from_iter(root.map(|root| (root, false))),
+            order,
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some((node, expanded)) = self.stack.pop() {
+            if expanded {
+                return Some(unsafe { entry(node) });
+            }
+
+            let (left, right) = unsafe { (node.as_ref().left, node.as_ref().right) };
+
+            match self.order {
+                Order::Pre => {
+                    self.stack.extend(right.map(|right| (right, false)));
+                    self.stack.extend(left.map(|left| (left, false)));
+                    return Some(unsafe { entry(node) });
+                }
+                Order::In => {
+                    self.stack.extend(right.map(|right| (right, false)));
+                    self.stack.push((node, true));
+                    self.stack.extend(left.map(|left| (left, false)));
+                }
+                Order::Post => {
+                    self.stack.push((node, true));
+                    self.stack.extend(right.map(|right| (right, false)));
+                    self.stack.extend(left.map(|left| (left, false)));
+                }
+            }
+        }
+
+        None
+    }
+}
+
+impl<'a, K, V> IntoIterator for &'a RbTree<K, V> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Checks ordering, parent links, the red rule and equal black heights,
+    // returning the black height.
+    fn check<K: Ord, V>(tree: &RbTree<K, V>) -> usize {
+        fn walk<K: Ord, V>(link: Link<K, V>, parent: Link<K, V>) -> usize {
+            let Some(node) = link else {
+                return 1;
+            };
+
+            unsafe {
+                let node = node.as_ref();
+                assert!(node.parent == parent, "broken parent link");
+
+                if node.color == Color::Red {
+                    assert!(!is_red(node.left) && !is_red(node.right), "red-red");
+                }
+
+                if let Some(left) = node.left {
+                    assert!(left.as_ref().key < node.key);
+                }
+
+                if let Some(right) = node.right {
+                    assert!(right.as_ref().key > node.key);
+                }
+
+                let lh = walk(node.left, link);
+                let rh = walk(node.right, link);
+                assert_eq!(lh, rh, "unequal black heights");
+
+                lh + (node.color == Color::Black) as usize
+            }
+        }
+
+        assert!(!is_red(tree.root), "red root");
+        walk(tree.root, None)
+    }
+
+    fn new_tree() -> RbTree<u32, u32> {
+        let mut tree = RbTree::new();
+
+        for key in [41, 38, 31, 12, 19, 8] {
+            tree.insert(key, key * 10);
+        }
+
+        tree
+    }
+
+    #[test]
+    fn insert_get() {
+        let mut tree = new_tree();
+        check(&tree);
+
+        assert_eq!(tree.len(), 6);
+        assert_eq!(tree.get(&19), Some(&190));
+        assert_eq!(tree.get(&20), None);
+        assert!(tree.contains_key(&8));
+
+        assert_eq!(tree.insert(19, 0), Some(190));
+        assert_eq!(tree.len(), 6);
+
+        *tree.get_mut(&41).unwrap() += 1;
+        assert_eq!(tree.get(&41), Some(&411));
+    }
+
+    #[test]
+    fn traversals() {
+        let tree = new_tree();
+        let keys = |iter: Iter<'_, u32, u32>| iter.map(|(k, _)| *k).collect::<Vec<_>>();
+
+        // CLRS figure 13.4 after the same insertions.
+        assert_eq!(keys(tree.inorder()), [8, 12, 19, 31, 38, 41]);
+        assert_eq!(keys(tree.preorder()), [38, 19, 12, 8, 31, 41]);
+        assert_eq!(keys(tree.postorder()), [8, 12, 31, 19, 41, 38]);
+        assert_eq!(keys(tree.iter()), keys(tree.inorder()));
+
+        let mut seen = Vec::new();
+
+        for (k, v) in &tree {
+            assert_eq!(*v, 10 * k);
+            seen.push(*k);
+        }
+
+        assert_eq!(seen, [8, 12, 19, 31, 38, 41]);
+
+        // Iterating never compares keys, so they need not be `Ord`.
+        let floats: RbTree<f64, ()> = RbTree::new();
+        let mut count = 0;
+
+        for _ in &floats {
+            count += 1;
+        }
+
+        assert_eq!(count, 0);
+    }
+
+    #[test]
+    fn min_max_pred_succ() {
+        let tree = new_tree();
+
+        assert_eq!(tree.min(), Some((&8, &80)));
+        assert_eq!(tree.max(), Some((&41, &410)));
+
+        assert_eq!(tree.successor(&19), Some((&31, &310)));
+        assert_eq!(tree.successor(&20), Some((&31, &310)));
+        assert_eq!(tree.successor(&41), None);
+
+        assert_eq!(tree.predecessor(&19), Some((&12, &120)));
+        assert_eq!(tree.predecessor(&13), Some((&12, &120)));
+        assert_eq!(tree.predecessor(&8), None);
+
+        let empty: RbTree<u32, u32> = RbTree::new();
+        assert_eq!(empty.min(), None);
+        assert_eq!(empty.max(), None);
+        assert_eq!(empty.successor(&1), None);
+    }
+
+    #[test]
+    fn sorted_input_stays_balanced() {
+        let mut tree = RbTree::new();
+
+        for key in 0..(1 << 14) {
+            tree.insert(key, ());
+        }
+
+        check(&tree);
+        assert!(tree.height() <= 2 * 15, "height = {}", tree.height());
+    }
+
+    #[test]
+    fn remove() {
+        let mut tree = RbTree::new();
+
+        for i in 0..1000u32 {
+            tree.insert((i * 7919) % 1000, i);
+        }
+
+        for i in 0..1000u32 {
+            let key = (i * 6007) % 1000;
+
+            if key % 3 != 0 {
+                assert!(tree.remove(&key).is_some());
+                assert_eq!(tree.remove(&key), None);
+            }
+
+            if i % 50 == 0 {
+                check(&tree);
+            }
+        }
+
+        check(&tree);
+        assert_eq!(tree.len(), 334);
+        assert!(tree.iter().map(|(k, _)| *k).eq((0..1000).step_by(3)));
+
+        for key in (0..1000).step_by(3) {
+            assert!(tree.remove(&key).is_some());
+        }
+
+        assert!(tree.is_empty());
+        assert_eq!(tree.height(), 0);
+    }
+}
@@ -0,0 +1,480 @@
+use std::cmp::Ordering;
+
+type Link<K, V> = Option<Box<Node<K, V>>>;
+
+struct Node<K, V> {
+    key: K,
+    value: V,
+    size: usize,
+    left: Link<K, V>,
+    right: Link<K, V>,
+}
+
+pub struct SplayTree<K, V> {
+    root: Link<K, V>,
+    rotations: usize,
+}
+
+impl<K, V> SplayTree<K, V> {
+    pub fn new() -> Self {
+        Self {
+            root: None,
+            rotations: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        size(&self.root)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    // Rotations and links performed by splaying so far. Splaying a node at
+    // depth d costs d of them.
+    pub fn rotations(&self) -> usize {
+        self.rotations
+    }
+
+    pub fn root(&self) -> Option<(&K, &V)> {
+        self.root.as_deref().map(|root| (&root.key, &root.value))
+    }
+
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        let mut iter = Iter { stack: Vec::new() };
+        iter.push_left(self.root.as_deref());
+        iter
+    }
+
+    fn splay_by(&mut self, cmp: impl Fn(&K) -> Ordering) {
+        if let Some(root) = self.root.take() {
+            self.root = Some(splay(root, cmp, &mut self.rotations));
+        }
+    }
+}
+
+impl<K: Ord, V> SplayTree<K, V> {
+    pub fn get(&mut self, key: &K) -> Option<&V> {
+        self.splay_by(|k| key.cmp(k));
+
+        match self.root.as_deref() {
+            Some(root) if root.key == *key => Some(&root.value),
+            _ => None,
+        }
+    }
+
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        self.splay_by(|k| key.cmp(k));
+
+        match self.root.as_deref_mut() {
+            Some(root) if root.key == *key => Some(&mut root.value),
+            _ => None,
+        }
+    }
+
+    pub fn contains_key(&mut self, key: &K) -> bool {
+        self.get(key).is_some()
+    }
+
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        self.splay_by(|k| key.cmp(k));
+
+        let mut node = Box::new(Node {
+            key,
+            value,
+            size: 1,
+            left: None,
+            right: None,
+        });
+
+        if let Some(mut root) = self.root.take() {
+            match node.key.cmp(&root.key) {
+                Ordering::Equal => {
+                    let old = std::mem::replace(&mut root.value, node.value);
+                    self.root = Some(root);
+                    return Some(old);
+                }
+                Ordering::Less => {
+                    node.left = root.left.take();
+                    update(&mut root);
+                    node.right = Some(root);
+                }
+                Ordering::Greater => {
+                    node.right = root.right.take();
+                    update(&mut root);
+                    node.left = Some(root);
+                }
+            }
+
+            update(&mut node);
+        }
+
+        self.root = Some(node);
+        None
+    }
+
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        self.splay_by(|k| key.cmp(k));
+
+        match self.root.take() {
+            Some(mut root) if root.key == *key => {
+                self.root = join(root.left.take(), root.right.take(), &mut self.rotations);
+                Some(root.value)
+            }
+            root => {
+                self.root = root;
+                None
+            }
+        }
+    }
+
+    // Moves every entry with a key greater than or equal to `key` into a new
+    // tree.
+    pub fn split(&mut self, key: &K) -> SplayTree<K, V> {
+        self.splay_by(|k| key.cmp(k));
+
+        let root = match self.root.take() {
+            Some(mut root) if root.key < *key => {
+                let right = root.right.take();
+                update(&mut root);
+                self.root = Some(root);
+                right
+            }
+            Some(mut root) => {
+                self.root = root.left.take();
+                update(&mut root);
+                Some(root)
+            }
+            None => None,
+        };
+
+        SplayTree { root, rotations: 0 }
+    }
+
+    // Appends `other`, all of whose keys must be greater than ours.
+    pub fn join(&mut self, mut other: SplayTree<K, V>) {
+        other.splay_by(|_| Ordering::Less);
+        self.splay_by(|_| Ordering::Greater);
+
+        if let (Some(root), Some(first)) = (self.root.as_deref(), other.root.as_deref()) {
+            assert!(root.key < first.key, "trees overlap");
+        }
+
+        self.rotations += other.rotations;
+        self.root = join(self.root.take(), other.root.take(), &mut self.rotations);
+    }
+}
+
+impl<K, V> Default for SplayTree<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Sequential access leaves the tree as a path, so tear it down without
+// recursion.
+impl<K, V> Drop for SplayTree<K, V> {
+    fn drop(&mut self) {
+        let mut stack = Vec::from_iter(self.root.take());
+
+        while let Some(mut node) = stack.pop() {
+            stack.extend(node.left.take());
+            stack.extend(node.right.take());
+        }
+    }
+}
+
+fn size<K, V>(link: &Link<K, V>) -> usize {
+    link.as_ref().map_or(0, |node| node.size)
+}
+
+fn update<K, V>(node: &mut Node<K, V>) {
+    node.size = 1 + size(&node.left) + size(&node.right);
+}
+
+// Joins two trees where every key in `left` is less than every key in
+// `right` by splaying the maximum of `left` to its root.
+fn join<K, V>(left: Link<K, V>, right: Link<K, V>, rotations: &mut usize) -> Link<K, V> {
+    let Some(left) = left else {
+        return right;
+    };
+
+    let mut root = splay(left, |_| Ordering::Greater, rotations);
+    root.right = right;
+    update(&mut root);
+    Some(root)
+}
+
+// Top-down splay (Sleator and Tarjan). `cmp` compares the target with a
+// node's key. Nodes less than the target are collected in `left` and chained
+// through their right links, nodes greater in `right` through their left
+// links, and both chains are hung under the final root at the end.
+fn splay<K, V>(
+    mut t: Box<Node<K, V>>,
+    cmp: impl Fn(&K) -> Ordering,
+    rotations: &mut usize,
+) -> Box<Node<K, V>> {
+    let mut left: Vec<Box<Node<K, V>>> = Vec::new();
+    let mut right: Vec<Box<Node<K, V>>> = Vec::new();
+
+    loop {
+        match cmp(&t.key) {
+            Ordering::Less => {
+                let Some(mut child) = t.left.take() else {
+                    break;
+                };
+
+                if cmp(&child.key) == Ordering::Less {
+                    // Zig-zig: rotate right before linking.
+                    t.left = child.right.take();
+                    update(&mut t);
+                    child.right = Some(t);
+                    t = child;
+                    *rotations += 1;
+
+                    match t.left.take() {
+                        Some(next) => child = next,
+                        None => break,
+                    }
+                }
+
+                right.push(t);
+                t = child;
+                *rotations += 1;
+            }
+            Ordering::Greater => {
+                let Some(mut child) = t.right.take() else {
+                    break;
+                };
+
+                if cmp(&child.key) == Ordering::Greater {
+                    // Zag-zag: rotate left before linking.
+                    t.right = child.left.take();
+                    update(&mut t);
+                    child.left = Some(t);
+                    t = child;
+                    *rotations += 1;
+
+                    match t.right.take() {
+                        Some(next) => child = next,
+                        None => break,
+                    }
+                }
+
+                left.push(t);
+                t = child;
+                *rotations += 1;
+            }
+            Ordering::Equal => break,
+        }
+    }
+
+    let mut acc = t.left.take();
+
+    for mut node in left.into_iter().rev() {
+        node.right = acc;
+        update(&mut node);
+        acc = Some(node);
+    }
+
+    t.left = acc;
+
+    let mut acc = t.right.take();
+
+    for mut node in right.into_iter().rev() {
+        node.left = acc;
+        update(&mut node);
+        acc = Some(node);
+    }
+
+    t.right = acc;
+    update(&mut t);
+    t
+}
+
+pub struct Iter<'a, K, V> {
+    stack: Vec<&'a Node<K, V>>,
+}
+
+impl<'a, K, V> Iter<'a, K, V> {
+    fn push_left(&mut self, mut node: Option<&'a Node<K, V>>) {
+        while let Some(curr) = node {
+            self.stack.push(curr);
+            node = curr.left.as_deref();
+        }
+    }
+}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.stack.pop()?;
+        self.push_left(node.right.as_deref());
+        Some((&node.key, &node.value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng::XorShift;
+
+    fn check<K: Ord, V>(link: &Link<K, V>) -> usize {
+        let Some(node) = link else {
+            return 0;
+        };
+
+        if let Some(left) = &node.left {
+            assert!(left.key < node.key);
+        }
+
+        if let Some(right) = &node.right {
+            assert!(right.key > node.key);
+        }
+
+        let size = 1 + check(&node.left) + check(&node.right);
+        assert_eq!(node.size, size);
+        size
+    }
+
+    fn keys(tree: &SplayTree<u32, u32>) -> Vec<u32> {
+        tree.iter().map(|(k, _)| *k).collect()
+    }
+
+    fn new_tree(n: u32) -> SplayTree<u32, u32> {
+        let mut tree = SplayTree::new();
+
+        for i in 0..n {
+            let key = (i * 7919) % n;
+            tree.insert(key, key);
+        }
+
+        tree
+    }
+
+    #[test]
+    fn access_splays_to_root() {
+        let mut tree = new_tree(1000);
+        check(&tree.root);
+        assert_eq!(tree.len(), 1000);
+
+        assert_eq!(tree.get(&500), Some(&500));
+        assert_eq!(tree.root().map(|(k, _)| *k), Some(500));
+        check(&tree.root);
+
+        assert_eq!(tree.get(&1000), None);
+        assert_eq!(tree.root().map(|(k, _)| *k), Some(999));
+
+        *tree.get_mut(&3).unwrap() = 42;
+        assert_eq!(tree.get(&3), Some(&42));
+        assert!(tree.contains_key(&0));
+        assert_eq!(tree.insert(3, 7), Some(42));
+        assert_eq!(keys(&tree), (0..1000).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn remove() {
+        let mut tree = new_tree(1000);
+
+        for key in (0..1000).filter(|k| k % 3 != 0) {
+            assert!(tree.remove(&key).is_some());
+            assert_eq!(tree.remove(&key), None);
+        }
+
+        check(&tree.root);
+        assert_eq!(keys(&tree), (0..1000).step_by(3).collect::<Vec<_>>());
+
+        for key in (0..1000).step_by(3) {
+            assert!(tree.remove(&key).is_some());
+        }
+
+        assert!(tree.is_empty());
+    }
+
+    #[test]
+    fn split_join() {
+        let mut tree = new_tree(100);
+
+        let mut upper = tree.split(&40);
+        check(&tree.root);
+        check(&upper.root);
+        assert_eq!(keys(&tree), (0..40).collect::<Vec<_>>());
+        assert_eq!(keys(&upper), (40..100).collect::<Vec<_>>());
+
+        let empty = upper.split(&500);
+        assert!(empty.is_empty());
+
+        let mut lower = tree.split(&0);
+        assert!(tree.is_empty());
+        assert_eq!(lower.len(), 40);
+
+        lower.join(tree);
+        lower.join(upper);
+        lower.join(SplayTree::new());
+        check(&lower.root);
+        assert_eq!(lower.len(), 100);
+        assert_eq!(keys(&lower), (0..100).collect::<Vec<_>>());
+    }
+
+    #[test]
+    #[should_panic(expected = "trees overlap")]
+    fn join_overlapping() {
+        let mut a = new_tree(10);
+        let b = new_tree(10);
+        a.join(b);
+    }
+
+    #[test]
+    fn sequential_insert_is_deep_but_safe() {
+        let mut tree = SplayTree::new();
+
+        for key in 0..100_000u32 {
+            tree.insert(key, key);
+        }
+
+        // Each insert lands at the root, so a path has built up.
+        assert_eq!(tree.rotations(), 0);
+        assert_eq!(tree.get(&0), Some(&0));
+        assert!(tree.rotations() >= 99_999);
+    }
+
+    // Repeatedly touching a small working set keeps it near the root, so it
+    // costs far fewer rotations than spreading the same number of accesses
+    // uniformly over all keys.
+    #[test]
+    fn hot_set_is_cheaper_than_uniform() {
+        const N: u64 = 10_000;
+        const ACCESSES: usize = 20_000;
+
+        let cost = |keys: &mut dyn FnMut() -> u64| {
+            let mut tree = SplayTree::new();
+
+            for i in 0..N {
+                tree.insert((i * 7919) % N, ());
+            }
+
+            let before = tree.rotations();
+
+            for _ in 0..ACCESSES {
+                assert!(tree.contains_key(&keys()));
+            }
+
+            tree.rotations() - before
+        };
+
+        let mut rng = XorShift::new(11);
+        let hot: Vec<u64> = (0..16).map(|_| rng.next_below(N)).collect();
+
+        let hot_cost = cost(&mut || hot[rng.next_below(hot.len() as u64) as usize]);
+        let uniform_cost = cost(&mut || rng.next_below(N));
+
+        assert!(
+            hot_cost * 3 < uniform_cost,
+            "hot = {}, uniform = {}",
+            hot_cost,
+            uniform_cost
+        );
+    }
+}
@@ -0,0 +1,381 @@
+use std::cmp::Ordering;
+
+use crate::rng::XorShift;
+
+type Link<K, V> = Option<Box<Node<K, V>>>;
+
+// A BST on keys that is also a max-heap on random priorities, which makes
+// its shape that of a BST built from a random insertion order.
+struct Node<K, V> {
+    key: K,
+    value: V,
+    priority: u64,
+    size: usize,
+    left: Link<K, V>,
+    right: Link<K, V>,
+}
+
+pub struct Treap<K, V> {
+    root: Link<K, V>,
+    rng: XorShift,
+}
+
+impl<K, V> Treap<K, V> {
+    pub fn new() -> Self {
+        Self {
+            root: None,
+            rng: XorShift::from_entropy(),
+        }
+    }
+
+    pub fn with_seed(seed: u64) -> Self {
+        Self {
+            root: None,
+            rng: XorShift::new(seed),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        size(&self.root)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    pub fn height(&self) -> usize {
+        let mut height = 0;
+        let mut stack = Vec::from_iter(self.root.as_deref().map(|root| (root, 1)));
+
+        while let Some((node, depth)) = stack.pop() {
+            height = height.max(depth);
+            stack.extend(node.left.as_deref().map(|left| (left, depth + 1)));
+            stack.extend(node.right.as_deref().map(|right| (right, depth + 1)));
+        }
+
+        height
+    }
+
+    pub fn first(&self) -> Option<(&K, &V)> {
+        let mut node = self.root.as_deref()?;
+
+        while let Some(left) = &node.left {
+            node = left;
+        }
+
+        Some((&node.key, &node.value))
+    }
+
+    pub fn last(&self) -> Option<(&K, &V)> {
+        let mut node = self.root.as_deref()?;
+
+        while let Some(right) = &node.right {
+            node = right;
+        }
+
+        Some((&node.key, &node.value))
+    }
+
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        let mut iter = Iter { stack: Vec::new() };
+        iter.push_left(self.root.as_deref());
+        iter
+    }
+}
+
+impl<K: Ord, V> Treap<K, V> {
+    pub fn get(&self, key: &K) -> Option<&V> {
+        let mut node = self.root.as_deref()?;
+
+        loop {
+            match key.cmp(&node.key) {
+                Ordering::Less => node = node.left.as_deref()?,
+                Ordering::Greater => node = node.right.as_deref()?,
+                Ordering::Equal => return Some(&node.value),
+            }
+        }
+    }
+
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        let mut node = self.root.as_deref_mut()?;
+
+        loop {
+            match key.cmp(&node.key) {
+                Ordering::Less => node = node.left.as_deref_mut()?,
+                Ordering::Greater => node = node.right.as_deref_mut()?,
+                Ordering::Equal => return Some(&mut node.value),
+            }
+        }
+    }
+
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.get(key).is_some()
+    }
+
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let (less, rest) = split(self.root.take(), &|k| *k < key);
+        let (equal, greater) = split(rest, &|k| *k <= key);
+
+        let (node, old) = match equal {
+            Some(mut node) => {
+                let old = std::mem::replace(&mut node.value, value);
+                (node, Some(old))
+            }
+            None => {
+                let node = Box::new(Node {
+                    key,
+                    value,
+                    priority: self.rng.next_u64(),
+                    size: 1,
+                    left: None,
+                    right: None,
+                });
+
+                (node, None)
+            }
+        };
+
+        self.root = merge(merge(less, Some(node)), greater);
+        old
+    }
+
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let (less, rest) = split(self.root.take(), &|k| k < key);
+        let (equal, greater) = split(rest, &|k| k <= key);
+
+        self.root = merge(less, greater);
+        equal.map(|node| node.value)
+    }
+
+    // Moves every entry with a key greater than or equal to `key` into a new
+    // treap, which draws its priorities from a generator seeded by this one.
+    pub fn split(&mut self, key: &K) -> Treap<K, V> {
+        let (less, rest) = split(self.root.take(), &|k| k < key);
+        self.root = less;
+
+        Treap {
+            root: rest,
+            rng: XorShift::new(self.rng.next_u64()),
+        }
+    }
+
+    // Appends `other`, all of whose keys must be greater than ours.
+    pub fn merge(&mut self, mut other: Treap<K, V>) {
+        if let (Some((last, _)), Some((first, _))) = (self.last(), other.first()) {
+            assert!(last < first, "treaps overlap");
+        }
+
+        self.root = merge(self.root.take(), other.root.take());
+    }
+}
+
+impl<K, V> Default for Treap<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn size<K, V>(link: &Link<K, V>) -> usize {
+    link.as_ref().map_or(0, |node| node.size)
+}
+
+fn update<K, V>(node: &mut Node<K, V>) {
+    node.size = 1 + size(&node.left) + size(&node.right);
+}
+
+// Splits into the keys for which `goes_left` holds and the rest. The
+// predicate must hold for a (possibly empty) prefix of the keys.
+fn split<K, V>(link: Link<K, V>, goes_left: &impl Fn(&K) -> bool) -> (Link<K, V>, Link<K, V>) {
+    let Some(mut node) = link else {
+        return (None, None);
+    };
+
+    if goes_left(&node.key) {
+        let (left, right) = split(node.right.take(), goes_left);
+        node.right = left;
+        update(&mut node);
+        (Some(node), right)
+    } else {
+        let (left, right) = split(node.left.take(), goes_left);
+        node.left = right;
+        update(&mut node);
+        (left, Some(node))
+    }
+}
+
+// Joins two treaps where every key in `a` is less than every key in `b`.
+fn merge<K, V>(a: Link<K, V>, b: Link<K, V>) -> Link<K, V> {
+    match (a, b) {
+        (None, b) => b,
+        (a, None) => a,
+        (Some(mut a), Some(mut b)) => {
+            if a.priority > b.priority {
+                a.right = merge(a.right.take(), Some(b));
+                update(&mut a);
+                Some(a)
+            } else {
+                b.left = merge(Some(a), b.left.take());
+                update(&mut b);
+                Some(b)
+            }
+        }
+    }
+}
+
+pub struct Iter<'a, K, V> {
+    stack: Vec<&'a Node<K, V>>,
+}
+
+impl<'a, K, V> Iter<'a, K, V> {
+    fn push_left(&mut self, mut node: Option<&'a Node<K, V>>) {
+        while let Some(curr) = node {
+            self.stack.push(curr);
+            node = curr.left.as_deref();
+        }
+    }
+}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.stack.pop()?;
+        self.push_left(node.right.as_deref());
+        Some((&node.key, &node.value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check<K: Ord, V>(link: &Link<K, V>) -> usize {
+        let Some(node) = link else {
+            return 0;
+        };
+
+        for child in [&node.left, &node.right].into_iter().flatten() {
+            assert!(child.priority <= node.priority, "heap order");
+        }
+
+        if let Some(left) = &node.left {
+            assert!(left.key < node.key);
+        }
+
+        if let Some(right) = &node.right {
+            assert!(right.key > node.key);
+        }
+
+        let size = 1 + check(&node.left) + check(&node.right);
+        assert_eq!(node.size, size);
+        size
+    }
+
+    fn keys(treap: &Treap<u32, u32>) -> Vec<u32> {
+        treap.iter().map(|(k, _)| *k).collect()
+    }
+
+    #[test]
+    fn insert_get_remove() {
+        let mut treap = Treap::with_seed(1);
+
+        for i in 0..1000u32 {
+            assert_eq!(treap.insert((i * 7919) % 1000, i), None);
+        }
+
+        check(&treap.root);
+        assert_eq!(treap.len(), 1000);
+        assert_eq!(treap.get(&0), Some(&0));
+        assert_eq!(treap.get(&1000), None);
+        assert_eq!(treap.insert(0, 7), Some(0));
+        *treap.get_mut(&0).unwrap() += 1;
+        assert_eq!(treap.get(&0), Some(&8));
+
+        for key in (0..1000).filter(|k| k % 4 != 0) {
+            assert!(treap.remove(&key).is_some());
+            assert_eq!(treap.remove(&key), None);
+        }
+
+        check(&treap.root);
+        assert_eq!(keys(&treap), (0..1000).step_by(4).collect::<Vec<_>>());
+        assert_eq!(treap.first(), Some((&0, &8)));
+        assert_eq!(treap.last().map(|(k, _)| *k), Some(996));
+    }
+
+    #[test]
+    fn sorted_input_stays_shallow() {
+        let mut treap = Treap::with_seed(2);
+
+        for key in 0..(1 << 14) {
+            treap.insert(key, ());
+        }
+
+        check(&treap.root);
+        assert!(treap.height() < 50, "height = {}", treap.height());
+    }
+
+    #[test]
+    fn split_merge() {
+        let mut treap = Treap::with_seed(3);
+
+        for key in 0..100u32 {
+            treap.insert(key, key);
+        }
+
+        let mut upper = treap.split(&60);
+        check(&treap.root);
+        check(&upper.root);
+        assert_eq!(keys(&treap), (0..60).collect::<Vec<_>>());
+        assert_eq!(keys(&upper), (60..100).collect::<Vec<_>>());
+        assert_eq!(upper.len(), 40);
+
+        let top = upper.split(&1000);
+        assert!(top.is_empty());
+        assert_eq!(upper.len(), 40);
+
+        upper.insert(61, 0);
+        treap.insert(10, 0);
+        treap.merge(upper);
+        check(&treap.root);
+        assert_eq!(treap.len(), 100);
+        assert_eq!(keys(&treap), (0..100).collect::<Vec<_>>());
+
+        treap.merge(Treap::new());
+        assert_eq!(treap.len(), 100);
+    }
+
+    #[test]
+    #[should_panic(expected = "treaps overlap")]
+    fn merge_overlapping() {
+        let mut a = Treap::with_seed(4);
+        let mut b = Treap::with_seed(5);
+        a.insert(5, ());
+        b.insert(5, ());
+        a.merge(b);
+    }
+
+    #[test]
+    fn seeded_shape_is_reproducible() {
+        let shape = |seed| {
+            let mut treap = Treap::with_seed(seed);
+
+            for key in 0..200u32 {
+                treap.insert(key, key);
+            }
+
+            let mut shape = Vec::new();
+            let mut stack = Vec::from_iter(treap.root.as_deref());
+
+            while let Some(node) = stack.pop() {
+                shape.push(node.key);
+                stack.extend(node.left.as_deref());
+                stack.extend(node.right.as_deref());
+            }
+
+            shape
+        };
+
+        assert_eq!(shape(9), shape(9));
+        assert_ne!(shape(9), shape(10));
+    }
+}
@@ -0,0 +1,547 @@
+// Both trees are keyed by the UTF-8 bytes of their keys, so byte order is
+// also the lexicographic order of the strings. A `Trie` labels every edge
+// with a single byte, a `RadixTree` merges chains of single-child nodes into
+// one edge labelled with the whole run.
+struct Node<L, V> {
+    value: Option<V>,
+    // Sorted by the first byte of the label, which is unique among siblings.
+    children: Vec<(L, Node<L, V>)>,
+}
+
+type TrieNode<V> = Node<[u8; 1], V>;
+type RadixNode<V> = Node<Box<[u8]>, V>;
+
+impl<L: AsRef<[u8]>, V> Node<L, V> {
+    fn new() -> Self {
+        Self {
+            value: None,
+            children: Vec::new(),
+        }
+    }
+
+    fn find(&self, byte: u8) -> Result<usize, usize> {
+        self.children
+            .binary_search_by_key(&byte, |(label, _)| label.as_ref()[0])
+    }
+
+    fn is_empty(&self) -> bool {
+        self.value.is_none() && self.children.is_empty()
+    }
+}
+
+pub struct Trie<V> {
+    root: TrieNode<V>,
+    len: usize,
+}
+
+impl<V> Trie<V> {
+    pub fn new() -> Self {
+        Self {
+            root: Node::new(),
+            len: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn insert(&mut self, key: &str, value: V) -> Option<V> {
+        let mut node = &mut self.root;
+
+        for &byte in key.as_bytes() {
+            let i = match node.find(byte) {
+                Ok(i) => i,
+                Err(i) => {
+                    node.children.insert(i, ([byte], Node::new()));
+                    i
+                }
+            };
+
+            node = &mut node.children[i].1;
+        }
+
+        let old = node.value.replace(value);
+
+        if old.is_none() {
+            self.len += 1;
+        }
+
+        old
+    }
+
+    pub fn get(&self, key: &str) -> Option<&V> {
+        self.node(key)?.value.as_ref()
+    }
+
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut V> {
+        let mut node = &mut self.root;
+
+        for &byte in key.as_bytes() {
+            let i = node.find(byte).ok()?;
+            node = &mut node.children[i].1;
+        }
+
+        node.value.as_mut()
+    }
+
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.get(key).is_some()
+    }
+
+    pub fn remove(&mut self, key: &str) -> Option<V> {
+        let value = remove_trie(&mut self.root, key.as_bytes())?;
+        self.len -= 1;
+        Some(value)
+    }
+
+    // Every entry whose key starts with `prefix`, in lexicographic order.
+    pub fn starts_with(&self, prefix: &str) -> StartsWith<'_, [u8; 1], V> {
+        StartsWith::new(self.node(prefix), prefix.as_bytes().to_vec())
+    }
+
+    pub fn iter(&self) -> StartsWith<'_, [u8; 1], V> {
+        self.starts_with("")
+    }
+
+    // The longest key that is a prefix of `s`, along with its value.
+    pub fn longest_prefix_of<'s>(&self, s: &'s str) -> Option<(&'s str, &V)> {
+        let mut node = &self.root;
+        let mut longest = node.value.as_ref().map(|value| (0, value));
+
+        for (depth, &byte) in s.as_bytes().iter().enumerate() {
+            let Ok(i) = node.find(byte) else {
+                break;
+            };
+
+            node = &node.children[i].1;
+
+            if let Some(value) = &node.value {
+                longest = Some((depth + 1, value));
+            }
+        }
+
+        longest.map(|(len, value)| (&s[..len], value))
+    }
+
+    fn node(&self, key: &str) -> Option<&TrieNode<V>> {
+        let mut node = &self.root;
+
+        for &byte in key.as_bytes() {
+            let i = node.find(byte).ok()?;
+            node = &node.children[i].1;
+        }
+
+        Some(node)
+    }
+}
+
+impl<V> Default for Trie<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Removes `key` below `node`, pruning the branches left without values.
+fn remove_trie<V>(node: &mut TrieNode<V>, key: &[u8]) -> Option<V> {
+    let Some((&byte, rest)) = key.split_first() else {
+        return node.value.take();
+    };
+
+    let i = node.find(byte).ok()?;
+    let value = remove_trie(&mut node.children[i].1, rest)?;
+
+    if node.children[i].1.is_empty() {
+        node.children.remove(i);
+    }
+
+    Some(value)
+}
+
+pub struct RadixTree<V> {
+    root: RadixNode<V>,
+    len: usize,
+}
+
+impl<V> RadixTree<V> {
+    pub fn new() -> Self {
+        Self {
+            root: Node::new(),
+            len: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn insert(&mut self, key: &str, value: V) -> Option<V> {
+        let mut node = &mut self.root;
+        let mut key = key.as_bytes();
+
+        while let Some(&byte) = key.first() {
+            let i = match node.find(byte) {
+                Ok(i) => i,
+                Err(i) => {
+                    let mut leaf = Node::new();
+                    leaf.value = Some(value);
+                    node.children.insert(i, (key.into(), leaf));
+                    self.len += 1;
+                    return None;
+                }
+            };
+
+            let (label, _) = &node.children[i];
+            let common = common_prefix(label, key);
+
+            if common < label.len() {
+                // Split the edge where the key diverges from it.
+                let (label, child) = node.children.remove(i);
+                let mut mid = Node::new();
+                mid.children.push((label[common..].into(), child));
+                node.children.insert(i, (label[..common].into(), mid));
+            }
+
+            node = &mut node.children[i].1;
+            key = &key[common..];
+        }
+
+        let old = node.value.replace(value);
+
+        if old.is_none() {
+            self.len += 1;
+        }
+
+        old
+    }
+
+    pub fn get(&self, key: &str) -> Option<&V> {
+        match self.node(key)? {
+            (node, []) => node.value.as_ref(),
+            _ => None,
+        }
+    }
+
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut V> {
+        let mut node = &mut self.root;
+        let mut key = key.as_bytes();
+
+        while let Some(&byte) = key.first() {
+            let i = node.find(byte).ok()?;
+            let (label, child) = &mut node.children[i];
+            key = key.strip_prefix(&label[..])?;
+            node = child;
+        }
+
+        node.value.as_mut()
+    }
+
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.get(key).is_some()
+    }
+
+    pub fn remove(&mut self, key: &str) -> Option<V> {
+        let value = remove_radix(&mut self.root, key.as_bytes())?;
+        self.len -= 1;
+        Some(value)
+    }
+
+    // Every entry whose key starts with `prefix`, in lexicographic order.
+    pub fn starts_with(&self, prefix: &str) -> StartsWith<'_, Box<[u8]>, V> {
+        match self.node(prefix) {
+            Some((node, rest)) => {
+                let mut key = prefix.as_bytes().to_vec();
+                key.extend_from_slice(rest);
+                StartsWith::new(Some(node), key)
+            }
+            None => StartsWith::new(None, Vec::new()),
+        }
+    }
+
+    pub fn iter(&self) -> StartsWith<'_, Box<[u8]>, V> {
+        self.starts_with("")
+    }
+
+    // The longest key that is a prefix of `s`, along with its value.
+    pub fn longest_prefix_of<'s>(&self, s: &'s str) -> Option<(&'s str, &V)> {
+        let mut node = &self.root;
+        let mut longest = node.value.as_ref().map(|value| (0, value));
+        let mut depth = 0;
+
+        while let Some(&byte) = s.as_bytes().get(depth) {
+            let Ok(i) = node.find(byte) else {
+                break;
+            };
+
+            let (label, child) = &node.children[i];
+
+            if !s.as_bytes()[depth..].starts_with(label) {
+                break;
+            }
+
+            node = child;
+            depth += label.len();
+
+            if let Some(value) = &node.value {
+                longest = Some((depth, value));
+            }
+        }
+
+        longest.map(|(len, value)| (&s[..len], value))
+    }
+
+    // Finds the node at or just below `key`, returning it with the part of
+    // its edge label that `key` did not cover.
+    fn node(&self, key: &str) -> Option<(&RadixNode<V>, &[u8])> {
+        let mut node = &self.root;
+        let mut key = key.as_bytes();
+
+        while let Some(&byte) = key.first() {
+            let i = node.find(byte).ok()?;
+            let (label, child) = &node.children[i];
+            let common = common_prefix(label, key);
+
+            if common == key.len() {
+                return Some((child, &label[common..]));
+            }
+
+            if common < label.len() {
+                return None;
+            }
+
+            node = child;
+            key = &key[common..];
+        }
+
+        Some((node, &[]))
+    }
+}
+
+impl<V> Default for RadixTree<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn common_prefix(a: &[u8], b: &[u8]) -> usize {
+    a.iter().zip(b).take_while(|(a, b)| a == b).count()
+}
+
+// Removes `key` below `node`, dropping edges to empty nodes and merging a
+// valueless node with its only child.
+fn remove_radix<V>(node: &mut RadixNode<V>, key: &[u8]) -> Option<V> {
+    let Some(&byte) = key.first() else {
+        return node.value.take();
+    };
+
+    let i = node.find(byte).ok()?;
+    let (label, child) = &mut node.children[i];
+    let value = remove_radix(child, key.strip_prefix(&label[..])?)?;
+
+    if child.is_empty() {
+        node.children.remove(i);
+    } else if child.value.is_none() && child.children.len() == 1 {
+        let (rest, grandchild) = child.children.pop().unwrap();
+        *label = [&label[..], &rest[..]].concat().into();
+        *child = grandchild;
+    }
+
+    Some(value)
+}
+
+pub struct StartsWith<'a, L, V> {
+    // Each frame holds a node, the index of its next child plus one (zero
+    // while its own value is pending) and the key length above its label.
+    stack: Vec<(&'a Node<L, V>, usize, usize)>,
+    key: Vec<u8>,
+}
+
+impl<'a, L, V> StartsWith<'a, L, V> {
+    fn new(node: Option<&'a Node<L, V>>, key: Vec<u8>) -> Self {
+        Self {
+            stack: node.map(|node| (node, 0, key.len())).into_iter().collect(),
+            key,
+        }
+    }
+}
+
+impl<'a, L: AsRef<[u8]>, V> Iterator for StartsWith<'a, L, V> {
+    type Item = (String, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let frame = self.stack.last_mut()?;
+            let (node, i) = (frame.0, frame.1);
+            frame.1 += 1;
+
+            if i == 0 {
+                if let Some(value) = &node.value {
+                    // Values only sit at the end of whole keys.
+                    let key = String::from_utf8(self.key.clone()).unwrap();
+                    return Some((key, value));
+                }
+
+                continue;
+            }
+
+            match node.children.get(i - 1) {
+                Some((label, child)) => {
+                    let len = self.key.len();
+                    self.key.extend_from_slice(label.as_ref());
+                    self.stack.push((child, 0, len));
+                }
+                None => {
+                    let (_, _, len) = self.stack.pop().unwrap();
+                    self.key.truncate(len);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COMMANDS: [&str; 9] = [
+        "status",
+        "stash",
+        "stash-pop",
+        "show",
+        "commit",
+        "co",
+        "checkout",
+        "cherry",
+        "zoé",
+    ];
+
+    fn keys<'a, L: AsRef<[u8]>, V: 'a>(iter: StartsWith<'a, L, V>) -> Vec<String> {
+        iter.map(|(key, _)| key).collect()
+    }
+
+    fn nodes<L, V>(node: &Node<L, V>) -> usize {
+        1 + node.children.iter().map(|(_, c)| nodes(c)).sum::<usize>()
+    }
+
+    #[test]
+    fn trie() {
+        let mut trie = Trie::new();
+
+        for (i, command) in COMMANDS.iter().enumerate() {
+            assert_eq!(trie.insert(command, i), None);
+        }
+
+        assert_eq!(trie.len(), 9);
+        assert_eq!(trie.insert("co", 10), Some(5));
+        *trie.get_mut("show").unwrap() += 1;
+        assert_eq!(trie.get("show"), Some(&4));
+        assert_eq!(trie.get("sho"), None);
+        assert!(!trie.contains_key("commits"));
+
+        let mut sorted = COMMANDS.map(String::from).to_vec();
+        sorted.sort();
+        assert_eq!(keys(trie.iter()), sorted);
+        assert_eq!(
+            keys(trie.starts_with("sta")),
+            ["stash", "stash-pop", "status"]
+        );
+        assert_eq!(keys(trie.starts_with("che")), ["checkout", "cherry"]);
+        assert_eq!(keys(trie.starts_with("zo")), ["zoé"]);
+        assert!(keys(trie.starts_with("x")).is_empty());
+
+        assert_eq!(trie.longest_prefix_of("commit -m"), Some(("commit", &4)));
+        assert_eq!(trie.longest_prefix_of("cob"), Some(("co", &10)));
+        assert_eq!(trie.longest_prefix_of("c"), None);
+
+        let before = nodes(&trie.root);
+        assert_eq!(trie.remove("stash-pop"), Some(2));
+        assert_eq!(trie.remove("stash-pop"), None);
+        assert_eq!(trie.remove("sta"), None);
+        assert_eq!(nodes(&trie.root), before - 4);
+        assert_eq!(trie.len(), 8);
+
+        for command in COMMANDS {
+            trie.remove(command);
+        }
+
+        assert!(trie.is_empty());
+        assert_eq!(nodes(&trie.root), 1);
+    }
+
+    #[test]
+    fn radix_tree() {
+        let mut tree = RadixTree::new();
+
+        for (i, command) in COMMANDS.iter().enumerate() {
+            assert_eq!(tree.insert(command, i), None);
+        }
+
+        assert_eq!(tree.len(), 9);
+        assert_eq!(tree.insert("co", 10), Some(5));
+        *tree.get_mut("show").unwrap() += 1;
+        assert_eq!(tree.get("show"), Some(&4));
+        assert_eq!(tree.get("sho"), None);
+        assert_eq!(tree.get("showing"), None);
+        assert!(!tree.contains_key("commits"));
+
+        let mut sorted = COMMANDS.map(String::from).to_vec();
+        sorted.sort();
+        assert_eq!(keys(tree.iter()), sorted);
+        assert_eq!(
+            keys(tree.starts_with("sta")),
+            ["stash", "stash-pop", "status"]
+        );
+        assert_eq!(keys(tree.starts_with("stas")), ["stash", "stash-pop"]);
+        assert_eq!(keys(tree.starts_with("che")), ["checkout", "cherry"]);
+        assert_eq!(keys(tree.starts_with("zo")), ["zoé"]);
+        assert!(keys(tree.starts_with("stx")).is_empty());
+
+        assert_eq!(tree.longest_prefix_of("commit -m"), Some(("commit", &4)));
+        assert_eq!(tree.longest_prefix_of("cob"), Some(("co", &10)));
+        assert_eq!(tree.longest_prefix_of("c"), None);
+
+        assert_eq!(tree.remove("stash-pop"), Some(2));
+        assert_eq!(tree.remove("stash-pop"), None);
+        assert_eq!(tree.remove("sta"), None);
+        assert_eq!(tree.len(), 8);
+
+        for command in COMMANDS {
+            tree.remove(command);
+        }
+
+        assert!(tree.is_empty());
+        assert_eq!(nodes(&tree.root), 1);
+    }
+
+    #[test]
+    fn radix_tree_stays_compressed() {
+        let mut tree = RadixTree::new();
+        tree.insert("test", 0);
+        tree.insert("team", 1);
+        tree.insert("toast", 2);
+
+        // "" -> "t" -> {"e" -> {"am", "st"}, "oast"}
+        assert_eq!(nodes(&tree.root), 6);
+
+        tree.remove("team");
+        // "" -> "t" -> {"est", "oast"}
+        assert_eq!(nodes(&tree.root), 4);
+
+        tree.remove("toast");
+        assert_eq!(nodes(&tree.root), 2);
+        assert_eq!(&*tree.root.children[0].0, b"test");
+        assert_eq!(keys(tree.iter()), ["test"]);
+
+        tree.insert("", 3);
+        assert_eq!(tree.get(""), Some(&3));
+        assert_eq!(tree.longest_prefix_of("tes"), Some(("", &3)));
+    }
+}